@@ -0,0 +1,150 @@
+//! Compares wall collider decompositions on generated rooms.
+//!
+//! Run with `cargo bench --bench wall_colliders`.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use bevy_rapier2d::rapier::prelude::*;
+use warp::{GridCoords, GridRect, WallDecomposition};
+
+const GRID_SIZE: f32 = 8.0;
+const STEPS: u32 = 300;
+
+/// A walled room with pseudo-random pillars and ragged wall segments.
+fn generate_room(width: i32, height: i32, seed: u64) -> HashSet<GridCoords> {
+    let mut state = seed;
+    let mut next = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        state >> 33
+    };
+
+    let mut cells = HashSet::new();
+    for x in 0..width {
+        for y in 0..height {
+            let border = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            if border || next() % 7 == 0 {
+                cells.insert(GridCoords { x, y });
+            }
+        }
+    }
+    cells
+}
+
+fn wall_colliders(rects: &[GridRect], compound: bool) -> Vec<Collider> {
+    let shape = |rect: &GridRect| {
+        let centre = rect.centre(GRID_SIZE);
+        let half_extents = rect.half_extents(GRID_SIZE);
+        (
+            Isometry::translation(centre.x, centre.y),
+            SharedShape::cuboid(half_extents.x, half_extents.y),
+        )
+    };
+
+    if compound {
+        vec![ColliderBuilder::compound(rects.iter().map(shape).collect()).build()]
+    } else {
+        rects
+            .iter()
+            .map(|rect| {
+                let (position, shape) = shape(rect);
+                ColliderBuilder::new(shape).position(position).build()
+            })
+            .collect()
+    }
+}
+
+fn physics_step_time(colliders: Vec<Collider>, width: i32, height: i32) -> Duration {
+    let mut bodies = RigidBodySet::new();
+    let mut collider_set = ColliderSet::new();
+
+    let walls = bodies.insert(RigidBodyBuilder::fixed().build());
+    for collider in colliders {
+        collider_set.insert_with_parent(collider, walls, &mut bodies);
+    }
+
+    // A handful of moving boxes, like the player, echoes and pushable boxes.
+    for i in 0..32 {
+        let body = bodies.insert(
+            RigidBodyBuilder::dynamic()
+                .translation(vector![
+                    (i % 8 + 2) as f32 * width as f32 * GRID_SIZE / 12.0,
+                    (i / 8 + 2) as f32 * height as f32 * GRID_SIZE / 8.0
+                ])
+                .linvel(vector![(i as f32).sin() * 100.0, (i as f32).cos() * 100.0])
+                .lock_rotations()
+                .build(),
+        );
+        collider_set.insert_with_parent(
+            ColliderBuilder::cuboid(7.0, 9.0).build(),
+            body,
+            &mut bodies,
+        );
+    }
+
+    let mut pipeline = PhysicsPipeline::new();
+    let mut islands = IslandManager::new();
+    let mut broad_phase = BroadPhase::new();
+    let mut narrow_phase = NarrowPhase::new();
+    let mut impulse_joints = ImpulseJointSet::new();
+    let mut multibody_joints = MultibodyJointSet::new();
+    let mut ccd_solver = CCDSolver::new();
+    let integration_parameters = IntegrationParameters::default();
+
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        pipeline.step(
+            &vector![0.0, 0.0],
+            &integration_parameters,
+            &mut islands,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut collider_set,
+            &mut impulse_joints,
+            &mut multibody_joints,
+            &mut ccd_solver,
+            &(),
+            &(),
+        );
+    }
+    start.elapsed() / STEPS
+}
+
+fn main() {
+    println!(
+        "{:<10} {:<14} {:>10} {:>16} {:>14}",
+        "room", "decomposition", "colliders", "decompose time", "step time"
+    );
+
+    for (width, height) in [(56, 24), (128, 64), (256, 256)] {
+        let cells = generate_room(width, height, 0x5eed);
+
+        for decomposition in [WallDecomposition::RowMerge, WallDecomposition::LargestFirst] {
+            let start = Instant::now();
+            let rects = decomposition.decompose(&cells, width, height);
+            let decompose_time = start.elapsed();
+
+            for compound in [false, true] {
+                let colliders = wall_colliders(&rects, compound);
+                let collider_count = colliders.len();
+                let step_time = physics_step_time(colliders, width, height);
+
+                println!(
+                    "{:<10} {:<14} {:>10} {:>16?} {:>14?}",
+                    format!("{width}x{height}"),
+                    format!(
+                        "{:?}{}",
+                        decomposition,
+                        if compound { "+compound" } else { "" }
+                    ),
+                    format!("{collider_count} ({} rects)", rects.len()),
+                    decompose_time,
+                    step_time,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+use super::*;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<SpriteAnimations>()
+            .init_asset_loader::<SpriteAnimationsLoader>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(animate_sprites)
+                    .into(),
+            );
+    }
+}
+
+/// A run of frames in a texture atlas, played one after the other.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct AnimationClip {
+    pub first: usize,
+    pub frames: usize,
+    /// Seconds each frame is shown for.
+    pub frame_time: f32,
+    #[serde(default = "looping_by_default")]
+    pub looping: bool,
+}
+
+fn looping_by_default() -> bool {
+    true
+}
+
+/// Named clips for one sprite sheet, loaded from an `.anim.ron` file.
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "5b0a3c9e-2f4d-4c61-9a8e-7d13f0e2b6a4"]
+pub struct SpriteAnimations {
+    pub clips: HashMap<String, AnimationClip>,
+}
+
+#[derive(Default)]
+struct SpriteAnimationsLoader;
+
+impl AssetLoader for SpriteAnimationsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        std::boxed::Box::pin(async move {
+            let animations = ron::de::from_bytes::<SpriteAnimations>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(animations));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["anim.ron"]
+    }
+}
+
+/// Plays clips from a `SpriteAnimations` asset on the entity's `TextureAtlasSprite`.
+#[derive(Component, Default)]
+pub struct SpriteAnimation {
+    animations: Handle<SpriteAnimations>,
+    clip: String,
+    frame: usize,
+    timer: Timer,
+}
+
+impl SpriteAnimation {
+    pub fn new(animations: Handle<SpriteAnimations>, clip: &str) -> Self {
+        Self {
+            animations,
+            clip: clip.to_string(),
+            ..default()
+        }
+    }
+
+    pub fn clip(&self) -> &str {
+        &self.clip
+    }
+
+    /// Switches to a clip from its first frame, carrying on if it is already playing.
+    pub fn play(&mut self, clip: &str) {
+        if self.clip != clip {
+            self.clip = clip.to_string();
+            self.frame = 0;
+            self.timer.reset();
+        }
+    }
+}
+
+fn animate_sprites(
+    mut sprite_query: Query<(&mut SpriteAnimation, &mut TextureAtlasSprite)>,
+    animations: Res<Assets<SpriteAnimations>>,
+    time: Res<Time>,
+) {
+    for (mut animation, mut sprite) in &mut sprite_query {
+        let clip = match animations
+            .get(&animation.animations)
+            .and_then(|animations| animations.clips.get(&animation.clip))
+        {
+            Some(clip) => *clip,
+            None => continue,
+        };
+
+        let frame_time = std::time::Duration::from_secs_f32(clip.frame_time);
+        if animation.timer.duration() != frame_time {
+            animation.timer = Timer::new(frame_time, true);
+        }
+
+        let finished = animation.timer.tick(time.delta()).times_finished() as usize;
+        animation.frame = if clip.looping {
+            (animation.frame + finished) % clip.frames.max(1)
+        } else {
+            (animation.frame + finished).min(clip.frames.saturating_sub(1))
+        };
+
+        sprite.index = clip.first + animation.frame;
+    }
+}
@@ -9,6 +9,7 @@ impl Plugin for AssetPlugin {
                 .with_collection::<LevelsAsset>()
                 .with_collection::<AudioAssets>()
                 .with_collection::<FontAssets>()
+                .with_collection::<AnimationAssets>()
                 .continue_to_state(GameState::Game),
         );
     }
@@ -24,6 +25,40 @@ pub struct LevelsAsset {
 pub struct AudioAssets {
     #[asset(path = "audio/music.ogg")]
     pub music: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/switch_press.wav")]
+    pub switch_press: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/switch_release.wav")]
+    pub switch_release: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/door_open.wav")]
+    pub door_open: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/door_close.wav")]
+    pub door_close: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/ability_start.wav")]
+    pub ability_start: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/ability_end.wav")]
+    pub ability_end: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/echo_spawn.wav")]
+    pub echo_spawn: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/echo_despawn.wav")]
+    pub echo_despawn: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/box_push.wav")]
+    pub box_push: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/footstep.wav")]
+    pub footstep: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/footstep_ice.wav")]
+    pub footstep_ice: Handle<AudioSource>,
+    #[asset(path = "audio/sfx/footstep_mud.wav")]
+    pub footstep_mud: Handle<AudioSource>,
+    #[asset(path = "audio/ambience/hum.wav")]
+    pub ambience: Handle<AudioSource>,
+    #[asset(path = "audio/ambience/drip.wav")]
+    pub drip: Handle<AudioSource>,
+    #[asset(path = "audio/ambience/machine.wav")]
+    pub machine: Handle<AudioSource>,
+    #[asset(path = "audio/ui/move.wav")]
+    pub ui_move: Handle<AudioSource>,
+    #[asset(path = "audio/ui/select.wav")]
+    pub ui_select: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection)]
@@ -31,3 +66,9 @@ pub struct FontAssets {
     #[asset(path = "fonts/game.ttf")]
     pub game: Handle<Font>,
 }
+
+#[derive(AssetCollection)]
+pub struct AnimationAssets {
+    #[asset(path = "animations/player.anim.ron")]
+    pub player: Handle<SpriteAnimations>,
+}
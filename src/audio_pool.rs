@@ -0,0 +1,118 @@
+use std::marker::PhantomData;
+
+use bevy::ecs::system::SystemParam;
+use bevy_kira_audio::{InstanceHandle, PlaybackState};
+
+use super::*;
+
+/// The most channels one pool can have, each needs its own channel type.
+pub const MAX_POOL_CHANNELS: usize = 6;
+
+/// One channel of the pool marked by `M`, so every sound or track in the pool can have its own
+/// volume, pitch and panning.
+pub struct PoolChannel<M, const N: usize>(PhantomData<M>);
+
+/// The audio channel calls the pools use, so channels of different types can be picked by index.
+pub trait PooledChannel {
+    fn play(&self, clip: Handle<AudioSource>) -> InstanceHandle;
+    fn play_looped(&self, clip: Handle<AudioSource>) -> InstanceHandle;
+    fn pause(&self);
+    fn resume(&self);
+    fn stop(&self);
+    fn set_volume(&self, volume: f32);
+    fn set_playback_rate(&self, playback_rate: f32);
+    fn set_panning(&self, panning: f32);
+    fn is_playing(&self, instance: &InstanceHandle) -> bool;
+}
+
+impl<T: Send + Sync + 'static> PooledChannel for AudioChannel<T> {
+    fn play(&self, clip: Handle<AudioSource>) -> InstanceHandle {
+        AudioChannel::play(self, clip)
+    }
+
+    fn play_looped(&self, clip: Handle<AudioSource>) -> InstanceHandle {
+        AudioChannel::play_looped(self, clip)
+    }
+
+    fn pause(&self) {
+        AudioChannel::pause(self);
+    }
+
+    fn resume(&self) {
+        AudioChannel::resume(self);
+    }
+
+    fn stop(&self) {
+        AudioChannel::stop(self);
+    }
+
+    fn set_volume(&self, volume: f32) {
+        AudioChannel::set_volume(self, volume);
+    }
+
+    fn set_playback_rate(&self, playback_rate: f32) {
+        AudioChannel::set_playback_rate(self, playback_rate);
+    }
+
+    fn set_panning(&self, panning: f32) {
+        AudioChannel::set_panning(self, panning);
+    }
+
+    fn is_playing(&self, instance: &InstanceHandle) -> bool {
+        !matches!(self.state(instance.clone()), PlaybackState::Stopped)
+    }
+}
+
+pub trait AddChannelPool {
+    /// Adds the first `size` channels of the pool marked by `M`.
+    fn add_channel_pool<M: Send + Sync + 'static>(&mut self, size: usize) -> &mut Self;
+}
+
+impl AddChannelPool for App {
+    fn add_channel_pool<M: Send + Sync + 'static>(&mut self, size: usize) -> &mut Self {
+        assert!(
+            size <= MAX_POOL_CHANNELS,
+            "Channel pools have at most {MAX_POOL_CHANNELS} channels"
+        );
+
+        for index in 0..size {
+            match index {
+                0 => self.add_audio_channel::<PoolChannel<M, 0>>(),
+                1 => self.add_audio_channel::<PoolChannel<M, 1>>(),
+                2 => self.add_audio_channel::<PoolChannel<M, 2>>(),
+                3 => self.add_audio_channel::<PoolChannel<M, 3>>(),
+                4 => self.add_audio_channel::<PoolChannel<M, 4>>(),
+                _ => self.add_audio_channel::<PoolChannel<M, 5>>(),
+            };
+        }
+        self
+    }
+}
+
+/// The channels of the pool marked by `M`, those past the pool's size are never added.
+#[derive(SystemParam)]
+pub struct ChannelPool<'w, 's, M: Send + Sync + 'static> {
+    channel_0: Option<Res<'w, AudioChannel<PoolChannel<M, 0>>>>,
+    channel_1: Option<Res<'w, AudioChannel<PoolChannel<M, 1>>>>,
+    channel_2: Option<Res<'w, AudioChannel<PoolChannel<M, 2>>>>,
+    channel_3: Option<Res<'w, AudioChannel<PoolChannel<M, 3>>>>,
+    channel_4: Option<Res<'w, AudioChannel<PoolChannel<M, 4>>>>,
+    channel_5: Option<Res<'w, AudioChannel<PoolChannel<M, 5>>>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<M: Send + Sync + 'static> ChannelPool<'_, '_, M> {
+    pub fn channel(&self, index: usize) -> &dyn PooledChannel {
+        let channel: Option<&dyn PooledChannel> = match index {
+            0 => self.channel_0.as_deref().map(|v| v as _),
+            1 => self.channel_1.as_deref().map(|v| v as _),
+            2 => self.channel_2.as_deref().map(|v| v as _),
+            3 => self.channel_3.as_deref().map(|v| v as _),
+            4 => self.channel_4.as_deref().map(|v| v as _),
+            5 => self.channel_5.as_deref().map(|v| v as _),
+            _ => None,
+        };
+        channel.expect("Channel pool index should be below the size it was added with")
+    }
+}
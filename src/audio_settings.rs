@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+pub struct AudioSettingsPlugin;
+
+impl Plugin for AudioSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_channel::<AmbienceChannel>()
+            .add_audio_channel::<UiChannel>()
+            .add_enter_system(GameState::Game, start_ambience)
+            .add_exit_system(GameState::Game, stop_ambience)
+            .add_system(apply_channel_volumes);
+    }
+}
+
+/// Music and ambience sit under the sound effects in the mix, music fades itself in and out.
+pub const MUSIC_VOLUME: f32 = 0.3;
+const AMBIENCE_VOLUME: f32 = 0.2;
+
+pub struct AmbienceChannel;
+pub struct UiChannel;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AudioBus {
+    Master,
+    Music,
+    Sfx,
+    Ambience,
+    Ui,
+}
+
+impl AudioBus {
+    pub const ALL: [AudioBus; 5] = [
+        AudioBus::Master,
+        AudioBus::Music,
+        AudioBus::Sfx,
+        AudioBus::Ambience,
+        AudioBus::Ui,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AudioBus::Master => "Master",
+            AudioBus::Music => "Music",
+            AudioBus::Sfx => "Effects",
+            AudioBus::Ambience => "Ambience",
+            AudioBus::Ui => "Interface",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BusVolume {
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Default for BusVolume {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl BusVolume {
+    fn level(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.volume
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: BusVolume,
+    pub music: BusVolume,
+    pub sfx: BusVolume,
+    pub ambience: BusVolume,
+    pub ui: BusVolume,
+}
+
+impl AudioSettings {
+    pub fn bus(&self, bus: AudioBus) -> &BusVolume {
+        match bus {
+            AudioBus::Master => &self.master,
+            AudioBus::Music => &self.music,
+            AudioBus::Sfx => &self.sfx,
+            AudioBus::Ambience => &self.ambience,
+            AudioBus::Ui => &self.ui,
+        }
+    }
+
+    pub fn bus_mut(&mut self, bus: AudioBus) -> &mut BusVolume {
+        match bus {
+            AudioBus::Master => &mut self.master,
+            AudioBus::Music => &mut self.music,
+            AudioBus::Sfx => &mut self.sfx,
+            AudioBus::Ambience => &mut self.ambience,
+            AudioBus::Ui => &mut self.ui,
+        }
+    }
+
+    /// The volume a bus plays at once the master volume and mutes are applied.
+    pub fn volume(&self, bus: AudioBus) -> f32 {
+        self.master.level() * self.bus(bus).level()
+    }
+}
+
+fn start_ambience(audio_assets: Res<AudioAssets>, ambience: Res<AudioChannel<AmbienceChannel>>) {
+    ambience.play_looped(audio_assets.ambience.clone());
+}
+
+fn stop_ambience(ambience: Res<AudioChannel<AmbienceChannel>>) {
+    ambience.stop();
+}
+
+fn apply_channel_volumes(
+    save_data: Res<SaveData>,
+    ambience: Res<AudioChannel<AmbienceChannel>>,
+    ui: Res<AudioChannel<UiChannel>>,
+) {
+    if save_data.is_changed() {
+        let settings = &save_data.audio;
+        ambience.set_volume(AMBIENCE_VOLUME * settings.volume(AudioBus::Ambience));
+        ui.set_volume(settings.volume(AudioBus::Ui));
+    }
+}
@@ -4,13 +4,31 @@ pub struct BoxPlugin;
 
 impl Plugin for BoxPlugin {
     fn build(&self, app: &mut App) {
-        app.register_ldtk_entity::<BoxBundle>("Box");
+        app.register_ldtk_entity::<BoxBundle>("Box")
+            .register_ldtk_entity::<MirrorBoxBundle>("MirrorBox");
     }
 }
 
 #[derive(Bundle, Default, LdtkEntity)]
 struct BoxBundle {
     r#box: Box,
+    surface: CurrentSurface,
+
+    #[bundle]
+    collider: BoxCollider,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct MirrorBoxBundle {
+    r#box: Box,
+    surface: CurrentSurface,
+
+    #[from_entity_instance]
+    mirror: Mirror,
 
     #[bundle]
     collider: BoxCollider,
@@ -32,6 +50,7 @@ struct BoxCollider {
     locked_axis: LockedAxes,
     friction: Friction,
     dampening: Damping,
+    collision_groups: CollisionGroups,
 }
 
 impl Default for BoxCollider {
@@ -47,6 +66,7 @@ impl Default for BoxCollider {
                 linear_damping: 15.0,
                 angular_damping: 0.0,
             },
+            collision_groups: CollisionGroups::new(BOX_GROUP, ALL_GROUPS),
         }
     }
 }
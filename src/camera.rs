@@ -1,3 +1,5 @@
+use bevy::render::camera::{ScalingMode, Viewport};
+
 use super::*;
 
 pub struct CameraPlugin;
@@ -9,80 +11,385 @@ impl Plugin for CameraPlugin {
                 level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
                     load_level_neighbors: true,
                 },
-                set_clear_color: SetClearColor::FromLevelBackground,
+                set_clear_color: SetClearColor::No,
                 ..Default::default()
             })
+            .init_resource::<CameraSettings>()
             .add_startup_system(spawn_camera)
+            .add_enter_system(PauseState::RoomTransition, freeze_physics)
+            .add_exit_system(PauseState::RoomTransition, unfreeze_physics)
+            .add_system(fit_viewport.run_in_state(GameState::Game))
+            .add_system(
+                follow_player
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused),
+            )
             .add_system(
-                fit_camera_inside_current_level
+                pan_between_rooms
                     .run_in_state(GameState::Game)
-                    .run_not_in_state(PauseState::Paused),
+                    .run_in_state(PauseState::RoomTransition),
             );
     }
 }
 
+/// Tunables for how the camera follows the player.
+pub struct CameraSettings {
+    /// Half size of the box around the camera focus the player can move in without moving it.
+    pub deadzone: Vec2,
+    /// Roughly how long the camera takes to catch up with its target.
+    pub smooth_time: f32,
+    /// How long the pan into a neighbouring room lasts.
+    pub transition_time: f32,
+    /// The most of a room shown at once, larger rooms scroll instead of being scaled down.
+    pub max_view: Vec2,
+    /// Space kept on screen around each player when several have to be framed.
+    pub player_margin: Vec2,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            deadzone: Vec2::new(16.0, 12.0),
+            smooth_time: 0.15,
+            transition_time: 0.6,
+            max_view: Vec2::new(256.0, 192.0),
+            player_margin: Vec2::new(24.0, 24.0),
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct CameraController {
+    /// Centre of the view before it is snapped to whole pixels.
+    centre: Vec2,
+    focus: Vec2,
+    velocity: Vec2,
+    room: Option<String>,
+    transition: Option<RoomPan>,
+    /// Screen pixels per world pixel.
+    pixel_scale: f32,
+    /// The area that has to fit in the view, the camera zooms out when it doesn't.
+    framed: Vec2,
+}
+
+struct RoomPan {
+    from: Vec2,
+    to: Vec2,
+    timer: Timer,
+}
+
 fn spawn_camera(mut commands: Commands) {
-    commands.spawn_bundle(Camera2dBundle::default());
+    commands
+        .spawn_bundle(Camera2dBundle::default())
+        .insert(CameraController::default())
+        .insert(CameraEffects::default());
+}
+
+fn freeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = false;
+}
+
+fn unfreeze_physics(mut rapier_config: ResMut<RapierConfiguration>) {
+    rapier_config.physics_pipeline_active = true;
+}
+
+/// The world space area the camera shows of a level and the whole number of screen pixels
+/// each world pixel is drawn with.
+///
+/// The scale drops as far as needed for `framed` to fit, so co-op players stay on screen.
+fn room_view(
+    ldtk_level: &LdtkLevel,
+    window_size: Vec2,
+    max_view: Vec2,
+    framed: Vec2,
+) -> (Vec2, f32) {
+    let level = &ldtk_level.level;
+    let level_size = Vec2::new(level.px_wid as f32, level.px_hei as f32);
+
+    let fit_room = (window_size / level_size.min(max_view))
+        .min_element()
+        .floor();
+    let fit_framed = (window_size / framed.max(Vec2::ONE)).min_element().floor();
+    let scale = fit_room.min(fit_framed).max(1.);
+
+    (level_size.min((window_size / scale).floor()), scale)
 }
 
-fn fit_camera_inside_current_level(
+fn primary_window_size(windows: &Windows) -> Option<Vec2> {
+    windows
+        .get_primary()
+        .map(|window| {
+            Vec2::new(
+                window.physical_width() as f32,
+                window.physical_height() as f32,
+            )
+        })
+        .filter(|size| size.min_element() > 0.)
+}
+
+/// Rounds a camera position so world pixels line up with screen pixels.
+fn snap_to_pixels(position: Vec2, pixel_scale: f32) -> Vec2 {
+    let pixel_scale = pixel_scale.max(1.);
+    (position * pixel_scale).round() / pixel_scale
+}
+
+/// Sizes the camera to the current room and centres an integer scaled viewport in the window,
+/// leaving the rest of the window as letterbox bars.
+fn fit_viewport(
     mut camera_query: Query<
         (
-            &mut bevy::render::camera::OrthographicProjection,
-            &mut Transform,
+            &mut Camera,
+            &mut OrthographicProjection,
+            &mut CameraController,
         ),
+        With<Camera2d>,
+    >,
+    level_query: Query<&Handle<LdtkLevel>>,
+    level_selection: Res<LevelSelection>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    windows: Res<Windows>,
+    settings: Res<CameraSettings>,
+) {
+    let window_size = match primary_window_size(&windows) {
+        Some(window_size) => window_size,
+        None => return,
+    };
+
+    for level_handle in &level_query {
+        if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
+            if !level_selection.is_match(&0, &ldtk_level.level) {
+                continue;
+            }
+
+            for (mut camera, mut orthographic_projection, mut controller) in &mut camera_query {
+                let (view, scale) = room_view(
+                    ldtk_level,
+                    window_size,
+                    settings.max_view,
+                    controller.framed,
+                );
+
+                let physical_size = (view * scale).as_uvec2();
+                let physical_position = ((window_size - view * scale) / 2.).as_uvec2();
+
+                let unchanged = camera.viewport.as_ref().map_or(false, |viewport| {
+                    viewport.physical_size == physical_size
+                        && viewport.physical_position == physical_position
+                });
+                if !unchanged {
+                    camera.viewport = Some(Viewport {
+                        physical_position,
+                        physical_size,
+                        ..default()
+                    });
+                }
+
+                if orthographic_projection.right != view.x || orthographic_projection.top != view.y
+                {
+                    orthographic_projection.scaling_mode = ScalingMode::None;
+                    orthographic_projection.bottom = 0.;
+                    orthographic_projection.left = 0.;
+                    orthographic_projection.right = view.x;
+                    orthographic_projection.top = view.y;
+                }
+
+                controller.pixel_scale = scale;
+            }
+        }
+    }
+}
+
+/// Keeps the view inside the level, centring it on any axis the level is smaller than the view.
+fn clamp_to_level(centre: Vec2, view: Vec2, level_position: Vec2, level_size: Vec2) -> Vec2 {
+    let min = level_position + view / 2.;
+    let max = level_position + level_size - view / 2.;
+
+    Vec2::new(
+        if min.x <= max.x {
+            centre.x.clamp(min.x, max.x)
+        } else {
+            level_position.x + level_size.x / 2.
+        },
+        if min.y <= max.y {
+            centre.y.clamp(min.y, max.y)
+        } else {
+            level_position.y + level_size.y / 2.
+        },
+    )
+}
+
+/// Critically damped spring towards `target`, the same curve as Unity's `SmoothDamp`.
+fn smooth_damp(
+    current: Vec2,
+    target: Vec2,
+    velocity: &mut Vec2,
+    smooth_time: f32,
+    delta: f32,
+) -> Vec2 {
+    let omega = 2. / smooth_time.max(0.0001);
+    let x = omega * delta;
+    let decay = 1. / (1. + x + 0.48 * x * x + 0.235 * x * x * x);
+
+    let change = current - target;
+    let temp = (*velocity + omega * change) * delta;
+    *velocity = (*velocity - omega * temp) * decay;
+
+    target + (change + temp) * decay
+}
+
+fn follow_player(
+    mut commands: Commands,
+    mut camera_query: Query<
+        (&mut Transform, &mut CameraController, &CameraEffects),
         (Without<Player>, With<Camera2d>),
     >,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    target_query: Query<&GlobalTransform>,
     level_query: Query<
         (&Transform, &Handle<LdtkLevel>),
         (Without<OrthographicProjection>, Without<Player>),
     >,
     level_selection: Res<LevelSelection>,
     ldtk_levels: Res<Assets<LdtkLevel>>,
+    settings: Res<CameraSettings>,
+    windows: Res<Windows>,
+    time: Res<Time>,
 ) {
-    const ASPECT_RATIO: f32 = 1. / 1.;
+    let window_size = match primary_window_size(&windows) {
+        Some(window_size) => window_size,
+        None => return,
+    };
 
-    if let Ok(Transform {
-        translation: player_translation,
-        ..
-    }) = player_query.get_single()
-    {
-        let player_translation = *player_translation;
+    // With two players the camera follows the middle of the box around them both.
+    let (players_min, players_max) = match player_query
+        .iter()
+        .map(|player_transform| player_transform.translation().truncate())
+        .fold(None, |bounds: Option<(Vec2, Vec2)>, translation| {
+            Some(bounds.map_or((translation, translation), |(min, max)| {
+                (min.min(translation), max.max(translation))
+            }))
+        }) {
+        Some(bounds) => bounds,
+        None => return,
+    };
+    let player_translation = (players_min + players_max) / 2.;
 
-        let (mut orthographic_projection, mut camera_transform) = camera_query.single_mut();
+    let (mut camera_transform, mut controller, effects) = camera_query.single_mut();
 
-        for (level_transform, level_handle) in &level_query {
-            if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
-                let level = &ldtk_level.level;
-                if level_selection.is_match(&0, level) {
-                    let level_ratio = level.px_wid as f32 / ldtk_level.level.px_hei as f32;
+    controller.framed = if player_query.iter().count() > 1 {
+        players_max - players_min + (PLAYER_HALF_EXTENTS + settings.player_margin) * 2.
+    } else {
+        Vec2::ZERO
+    };
 
-                    orthographic_projection.scaling_mode = bevy::render::camera::ScalingMode::None;
-                    orthographic_projection.bottom = 0.;
-                    orthographic_projection.left = 0.;
-                    if level_ratio > ASPECT_RATIO {
-                        orthographic_projection.top = (level.px_hei as f32 / 9.).round() * 9.;
-                        orthographic_projection.right = orthographic_projection.top * ASPECT_RATIO;
-                        camera_transform.translation.x = (player_translation.x
-                            - level_transform.translation.x
-                            - orthographic_projection.right / 2.)
-                            .clamp(0., level.px_wid as f32 - orthographic_projection.right);
-                        camera_transform.translation.y = 0.;
-                    } else {
-                        orthographic_projection.right = (level.px_wid as f32 / 16.).round() * 16.;
-                        orthographic_projection.top = orthographic_projection.right / ASPECT_RATIO;
-                        camera_transform.translation.y = (player_translation.y
-                            - level_transform.translation.y
-                            - orthographic_projection.top / 2.)
-                            .clamp(0., level.px_hei as f32 - orthographic_projection.top);
-                        camera_transform.translation.x = 0.;
-                    }
-
-                    camera_transform.translation.x += level_transform.translation.x;
-                    camera_transform.translation.y += level_transform.translation.y;
+    let focus_target = effects
+        .focus_target()
+        .and_then(|target| target_query.get(target).ok())
+        .map(|target_transform| target_transform.translation().truncate());
+
+    for (level_transform, level_handle) in &level_query {
+        if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
+            let level = &ldtk_level.level;
+            if !level_selection.is_match(&0, level) {
+                continue;
+            }
+
+            let (view, scale) = room_view(
+                ldtk_level,
+                window_size,
+                settings.max_view,
+                controller.framed,
+            );
+            let level_position = level_transform.translation.truncate();
+            let level_size = Vec2::new(level.px_wid as f32, level.px_hei as f32);
+
+            if controller.room.as_ref() != Some(&level.iid) {
+                let to = clamp_to_level(player_translation, view, level_position, level_size);
+
+                controller.focus = player_translation;
+                controller.velocity = Vec2::ZERO;
+
+                if controller.room.is_some() {
+                    controller.transition = Some(RoomPan {
+                        from: controller.centre,
+                        to,
+                        timer: Timer::from_seconds(settings.transition_time, false),
+                    });
+                    commands.insert_resource(NextState(PauseState::RoomTransition));
+                } else {
+                    // The camera transform sits at the bottom left of the view.
+                    let corner = snap_to_pixels(to - view / 2., scale);
+                    camera_transform.translation.x = corner.x;
+                    camera_transform.translation.y = corner.y;
+                    controller.centre = to;
                 }
+
+                controller.room = Some(level.iid.clone());
+            } else {
+                let offset = player_translation - controller.focus;
+                controller.focus += offset - offset.clamp(-settings.deadzone, settings.deadzone);
+
+                let target = clamp_to_level(
+                    focus_target.unwrap_or(controller.focus),
+                    view,
+                    level_position,
+                    level_size,
+                );
+                let mut velocity = controller.velocity;
+                let centre = smooth_damp(
+                    controller.centre,
+                    target,
+                    &mut velocity,
+                    settings.smooth_time,
+                    time.delta_seconds(),
+                );
+                controller.velocity = velocity;
+                controller.centre = centre;
+
+                let corner = snap_to_pixels(centre - view / 2., scale);
+                camera_transform.translation.x = corner.x;
+                camera_transform.translation.y = corner.y;
+            }
+        }
+    }
+}
+
+fn pan_between_rooms(
+    mut commands: Commands,
+    mut camera_query: Query<
+        (
+            &OrthographicProjection,
+            &mut Transform,
+            &mut CameraController,
+        ),
+        With<Camera2d>,
+    >,
+    time: Res<Time>,
+) {
+    for (orthographic_projection, mut camera_transform, mut controller) in &mut camera_query {
+        let view = Vec2::new(orthographic_projection.right, orthographic_projection.top);
+        let controller = &mut *controller;
+
+        let finished = match &mut controller.transition {
+            Some(pan) => {
+                pan.timer.tick(time.delta());
+
+                let t = pan.timer.percent();
+                let eased = t * t * (3. - 2. * t);
+                controller.centre = pan.from.lerp(pan.to, eased);
+
+                let corner = snap_to_pixels(controller.centre - view / 2., controller.pixel_scale);
+                camera_transform.translation.x = corner.x;
+                camera_transform.translation.y = corner.y;
+
+                pan.timer.finished()
             }
+            None => true,
+        };
+
+        if finished {
+            controller.transition = None;
+            commands.insert_resource(NextState(PauseState::Unpaused));
         }
     }
 }
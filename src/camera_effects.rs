@@ -0,0 +1,144 @@
+use bevy::transform::TransformSystem;
+
+use super::*;
+
+pub struct CameraEffectsPlugin;
+
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<CameraShakeEvent>()
+            .add_event::<CameraZoomEvent>()
+            .add_event::<CameraFocusEvent>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .with_system(receive_camera_effects)
+                    .into(),
+            )
+            // Runs after the camera has followed the player so the effects sit on top of it.
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                apply_camera_effects.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+const MAX_SHAKE_OFFSET: f32 = 6.0;
+const TRAUMA_DECAY: f32 = 1.5;
+
+/// Adds trauma to the camera, which shakes it by the square of the total trauma.
+pub struct CameraShakeEvent(pub f32);
+
+/// Briefly zooms the camera in by a factor, easing back out over the duration.
+pub struct CameraZoomEvent {
+    pub zoom: f32,
+    pub duration: f32,
+}
+
+/// Pans the camera to an entity for a while if it is off screen, then goes back to the player.
+pub struct CameraFocusEvent {
+    pub target: Entity,
+    pub duration: f32,
+}
+
+#[derive(Component, Default)]
+pub struct CameraEffects {
+    trauma: f32,
+    zoom: Option<(f32, Timer)>,
+    focus: Option<(Entity, Timer)>,
+}
+
+impl CameraEffects {
+    /// The entity the camera should look at instead of the player.
+    pub fn focus_target(&self) -> Option<Entity> {
+        self.focus.as_ref().map(|(target, _)| *target)
+    }
+}
+
+fn receive_camera_effects(
+    mut camera_query: Query<
+        (&mut CameraEffects, &Transform, &OrthographicProjection),
+        With<Camera2d>,
+    >,
+    target_query: Query<&GlobalTransform>,
+    mut shake_events: EventReader<CameraShakeEvent>,
+    mut zoom_events: EventReader<CameraZoomEvent>,
+    mut focus_events: EventReader<CameraFocusEvent>,
+) {
+    for (mut effects, camera_transform, orthographic_projection) in &mut camera_query {
+        for CameraShakeEvent(trauma) in shake_events.iter() {
+            effects.trauma = (effects.trauma + trauma).min(1.0);
+        }
+
+        for event in zoom_events.iter() {
+            effects.zoom = Some((event.zoom, Timer::from_seconds(event.duration, false)));
+        }
+
+        let view_min = camera_transform.translation.truncate();
+        let view_max =
+            view_min + Vec2::new(orthographic_projection.right, orthographic_projection.top);
+
+        for event in focus_events.iter() {
+            if let Ok(target_transform) = target_query.get(event.target) {
+                let target = target_transform.translation().truncate();
+                let on_screen = target.cmpge(view_min).all() && target.cmple(view_max).all();
+
+                if !on_screen {
+                    effects.focus =
+                        Some((event.target, Timer::from_seconds(event.duration, false)));
+                }
+            }
+        }
+    }
+}
+
+fn apply_camera_effects(
+    mut camera_query: Query<
+        (&mut CameraEffects, &mut Transform, &OrthographicProjection),
+        With<Camera2d>,
+    >,
+    pause_state: Res<CurrentState<PauseState>>,
+    time: Res<Time>,
+) {
+    if !matches!(
+        pause_state.0,
+        PauseState::Unpaused | PauseState::RoomTransition
+    ) {
+        return;
+    }
+
+    for (mut effects, mut camera_transform, orthographic_projection) in &mut camera_query {
+        let view = Vec2::new(orthographic_projection.right, orthographic_projection.top);
+
+        if let Some((_, timer)) = &mut effects.focus {
+            if timer.tick(time.delta()).finished() {
+                effects.focus = None;
+            }
+        }
+
+        let mut scale = 1.0;
+        if let Some((zoom, timer)) = &mut effects.zoom {
+            timer.tick(time.delta());
+            scale = 1.0 / (1.0 + (*zoom - 1.0) * timer.percent_left());
+
+            if timer.finished() {
+                effects.zoom = None;
+            }
+        }
+
+        // The projection is anchored at the bottom left, so keep the zoom centred on the view.
+        camera_transform.scale = Vec3::new(scale, scale, 1.0);
+        camera_transform.translation.x += view.x * (1.0 - scale) / 2.0;
+        camera_transform.translation.y += view.y * (1.0 - scale) / 2.0;
+
+        if effects.trauma > 0.0 {
+            let t = time.seconds_since_startup() as f32;
+            let shake = effects.trauma * effects.trauma * MAX_SHAKE_OFFSET;
+
+            camera_transform.translation.x += shake * ((t * 47.0).sin() + (t * 31.0).cos()) / 2.0;
+            camera_transform.translation.y += shake * ((t * 53.0).cos() + (t * 29.0).sin()) / 2.0;
+
+            effects.trauma = (effects.trauma - TRAUMA_DECAY * time.delta_seconds()).max(0.0);
+        }
+    }
+}
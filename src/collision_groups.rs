@@ -0,0 +1,53 @@
+//! Rapier collision group bits shared by every collider in the game.
+
+use super::*;
+
+pub const PLAYER_GROUP: u32 = 1 << 0;
+pub const ECHO_GROUP: u32 = 1 << 1;
+pub const BOX_GROUP: u32 = 1 << 2;
+pub const WALL_GROUP: u32 = 1 << 3;
+pub const DOOR_GROUP: u32 = 1 << 4;
+pub const SENSOR_GROUP: u32 = 1 << 5;
+pub const HAZARD_GROUP: u32 = 1 << 6;
+
+pub const ALL_GROUPS: u32 = u32::MAX;
+
+/// Everything that moves around a level and can press switches.
+pub const BODY_GROUPS: u32 = PLAYER_GROUP | ECHO_GROUP | BOX_GROUP;
+
+/// Bodies that pass through an otherwise solid entity, read from its optional `PassableBy`
+/// LDtk field.
+#[derive(Component, Default, Clone, Copy)]
+pub struct PassableBy(pub u32);
+
+impl PassableBy {
+    pub fn collision_groups(&self, membership: u32) -> CollisionGroups {
+        CollisionGroups::new(membership, ALL_GROUPS & !self.0)
+    }
+}
+
+impl From<EntityInstance> for PassableBy {
+    fn from(entity_instance: EntityInstance) -> Self {
+        match entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "PassableBy")
+            .map(|v| &v.value)
+        {
+            Some(FieldValue::Enums(bodies)) => Self(
+                bodies
+                    .iter()
+                    .flatten()
+                    .map(|body| match body.as_str() {
+                        "Player" => PLAYER_GROUP,
+                        "Echo" => ECHO_GROUP,
+                        "Box" => BOX_GROUP,
+                        _ => panic!("PassableBy field values must be Player, Echo or Box"),
+                    })
+                    .fold(0, |groups, group| groups | group),
+            ),
+            Some(_) => panic!("PassableBy field must be an array of enums"),
+            None => Self(0),
+        }
+    }
+}
@@ -0,0 +1,827 @@
+use std::{collections::HashMap, fmt, hash::Hash};
+
+use bevy::input::gamepad::{GamepadEvent, GamepadEventType};
+use leafwing_input_manager::{axislike::SingleAxis, user_input::UserInput};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+const CONTROLS_PATH: &str = "controls.ron";
+/// How far a stick has to be pushed before it counts as pressed.
+const STICK_DEADZONE: f32 = 0.2;
+/// Sticks are only captured as a new binding once pushed most of the way.
+const STICK_CAPTURE_THRESHOLD: f32 = 0.6;
+/// Menu actions that are also read during play, so they can't share a binding with a player action.
+const IN_GAME_MENU_ACTIONS: [PauseMenuAction; 1] = [PauseMenuAction::Open];
+
+const STICK_AXES: [GamepadAxisType; 4] = [
+    GamepadAxisType::LeftStickX,
+    GamepadAxisType::LeftStickY,
+    GamepadAxisType::RightStickX,
+    GamepadAxisType::RightStickY,
+];
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Controls::load())
+            .init_resource::<Rebinding>()
+            .init_resource::<ConnectedGamepads>()
+            .add_system(track_gamepads)
+            .add_enter_system(PauseState::Controls, spawn_controls_menu)
+            .add_exit_system(PauseState::Controls, despawn_entities_with::<ControlsMenu>)
+            .add_exit_system(PauseState::Controls, save_controls)
+            .add_system(apply_player_controls)
+            .add_system(apply_menu_controls)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Controls)
+                    .with_system(rebind_controls)
+                    .with_system(update_controls_menu)
+                    .into(),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    GamepadButton(GamepadButtonType),
+    GamepadAxis(GamepadAxisType, AxisDirection),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+impl From<Binding> for UserInput {
+    fn from(binding: Binding) -> Self {
+        match binding {
+            Binding::Key(key) => key.into(),
+            Binding::GamepadButton(button) => button.into(),
+            Binding::GamepadAxis(axis, AxisDirection::Positive) => {
+                SingleAxis::positive_only(axis, STICK_DEADZONE).into()
+            }
+            Binding::GamepadAxis(axis, AxisDirection::Negative) => {
+                SingleAxis::negative_only(axis, STICK_DEADZONE).into()
+            }
+        }
+    }
+}
+
+impl Binding {
+    fn is_key(&self) -> bool {
+        matches!(self, Binding::Key(_))
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{key:?}"),
+            Binding::GamepadButton(button) => write!(f, "Pad {button:?}"),
+            Binding::GamepadAxis(axis, AxisDirection::Positive) => write!(f, "Pad {axis:?}+"),
+            Binding::GamepadAxis(axis, AxisDirection::Negative) => write!(f, "Pad {axis:?}-"),
+        }
+    }
+}
+
+/// Input bindings for every action, saved separately from game progress.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Controls {
+    pub player: HashMap<PlayerAction, Vec<Binding>>,
+    /// The second player's bindings in co-op, their keys also move the first player when alone.
+    pub player_two: HashMap<PlayerAction, Vec<Binding>>,
+    pub menu: HashMap<PauseMenuAction, Vec<Binding>>,
+    /// Gamepad bindings of single gamepads by id, used instead of the gamepad bindings of
+    /// whichever player the gamepad is assigned to.
+    pub gamepad_profiles: HashMap<usize, HashMap<PlayerAction, Vec<Binding>>>,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        use AxisDirection::*;
+        use Binding::{GamepadAxis as Axis, GamepadButton as Button, Key};
+        use GamepadAxisType::*;
+        use GamepadButtonType::*;
+
+        Self {
+            player: HashMap::from([
+                (
+                    PlayerAction::Up,
+                    vec![Key(KeyCode::W), Button(DPadUp), Axis(LeftStickY, Positive)],
+                ),
+                (
+                    PlayerAction::Down,
+                    vec![
+                        Key(KeyCode::S),
+                        Button(DPadDown),
+                        Axis(LeftStickY, Negative),
+                    ],
+                ),
+                (
+                    PlayerAction::Left,
+                    vec![
+                        Key(KeyCode::A),
+                        Button(DPadLeft),
+                        Axis(LeftStickX, Negative),
+                    ],
+                ),
+                (
+                    PlayerAction::Right,
+                    vec![
+                        Key(KeyCode::D),
+                        Button(DPadRight),
+                        Axis(LeftStickX, Positive),
+                    ],
+                ),
+                (
+                    PlayerAction::Ability,
+                    vec![Key(KeyCode::Space), Button(South)],
+                ),
+                (PlayerAction::Restart, vec![Key(KeyCode::R), Button(North)]),
+            ]),
+            player_two: HashMap::from([
+                (
+                    PlayerAction::Up,
+                    vec![Key(KeyCode::Up), Button(DPadUp), Axis(LeftStickY, Positive)],
+                ),
+                (
+                    PlayerAction::Down,
+                    vec![
+                        Key(KeyCode::Down),
+                        Button(DPadDown),
+                        Axis(LeftStickY, Negative),
+                    ],
+                ),
+                (
+                    PlayerAction::Left,
+                    vec![
+                        Key(KeyCode::Left),
+                        Button(DPadLeft),
+                        Axis(LeftStickX, Negative),
+                    ],
+                ),
+                (
+                    PlayerAction::Right,
+                    vec![
+                        Key(KeyCode::Right),
+                        Button(DPadRight),
+                        Axis(LeftStickX, Positive),
+                    ],
+                ),
+                (
+                    PlayerAction::Ability,
+                    vec![Key(KeyCode::RShift), Button(South)],
+                ),
+                (
+                    PlayerAction::Restart,
+                    vec![Key(KeyCode::RControl), Button(North)],
+                ),
+            ]),
+            menu: HashMap::from([
+                (
+                    PauseMenuAction::Open,
+                    vec![Key(KeyCode::Escape), Button(Start)],
+                ),
+                (
+                    PauseMenuAction::Close,
+                    vec![Key(KeyCode::Return), Button(East)],
+                ),
+                (
+                    PauseMenuAction::Up,
+                    vec![Key(KeyCode::Up), Key(KeyCode::W), Button(DPadUp)],
+                ),
+                (
+                    PauseMenuAction::Down,
+                    vec![Key(KeyCode::Down), Key(KeyCode::S), Button(DPadDown)],
+                ),
+                (
+                    PauseMenuAction::Left,
+                    vec![Key(KeyCode::Left), Key(KeyCode::A), Button(DPadLeft)],
+                ),
+                (
+                    PauseMenuAction::Right,
+                    vec![Key(KeyCode::Right), Key(KeyCode::D), Button(DPadRight)],
+                ),
+                (
+                    PauseMenuAction::ToggleMute,
+                    vec![Key(KeyCode::Space), Button(West)],
+                ),
+                (
+                    PauseMenuAction::Controls,
+                    vec![Key(KeyCode::Tab), Button(Select)],
+                ),
+                (
+                    PauseMenuAction::ToggleCoop,
+                    vec![Key(KeyCode::C), Button(North)],
+                ),
+            ]),
+            gamepad_profiles: HashMap::new(),
+        }
+    }
+}
+
+impl Controls {
+    fn load() -> Self {
+        std::fs::read_to_string(CONTROLS_PATH)
+            .ok()
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn write(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => {
+                if let Err(error) = std::fs::write(CONTROLS_PATH, contents) {
+                    warn!("Could not write controls file: {error}");
+                }
+            }
+            Err(error) => warn!("Could not serialize controls: {error}"),
+        }
+    }
+
+    /// The map for one player, listening to their own gamepad if they have one.
+    pub fn player_input_map(
+        &self,
+        player: PlayerNumber,
+        coop: bool,
+        gamepad: Option<Gamepad>,
+    ) -> InputMap<PlayerAction> {
+        let mut bindings = self.profile_bindings(player, gamepad);
+
+        // Playing alone the keyboard is not shared, so both halves of it move the one player.
+        if !coop {
+            for (action, action_bindings) in &self.player_two {
+                let keys = action_bindings.iter().filter(|v| v.is_key());
+                bindings.entry(*action).or_default().extend(keys);
+            }
+        }
+
+        input_map(&bindings, gamepad)
+    }
+
+    pub fn menu_input_map(&self, gamepad: Option<Gamepad>) -> InputMap<PauseMenuAction> {
+        input_map(&self.menu, gamepad)
+    }
+
+    fn player_bindings(&self, player: PlayerNumber) -> &HashMap<PlayerAction, Vec<Binding>> {
+        match player.0 {
+            0 => &self.player,
+            _ => &self.player_two,
+        }
+    }
+
+    /// The player's bindings, with the gamepad ones taken from the gamepad's profile if it has one.
+    fn profile_bindings(
+        &self,
+        player: PlayerNumber,
+        gamepad: Option<Gamepad>,
+    ) -> HashMap<PlayerAction, Vec<Binding>> {
+        let bindings = self.player_bindings(player);
+        match gamepad.and_then(|gamepad| self.gamepad_profiles.get(&gamepad.id)) {
+            Some(profile) => PlayerAction::variants()
+                .map(|action| {
+                    let keys = bindings.get(&action).into_iter().flatten();
+                    let buttons = profile.get(&action).into_iter().flatten();
+                    let action_bindings = keys
+                        .filter(|v| v.is_key())
+                        .chain(buttons)
+                        .copied()
+                        .collect();
+                    (action, action_bindings)
+                })
+                .collect(),
+            None => bindings.clone(),
+        }
+    }
+
+    fn bindings(&self, target: RebindTarget, gamepad: Option<Gamepad>) -> Vec<Binding> {
+        match target {
+            RebindTarget::Player(action) => self
+                .profile_bindings(PlayerNumber(0), gamepad)
+                .remove(&action),
+            RebindTarget::PlayerTwo(action) => self
+                .profile_bindings(PlayerNumber(1), gamepad)
+                .remove(&action),
+            RebindTarget::Menu(action) => self.menu.get(&action).cloned(),
+        }
+        .unwrap_or_default()
+    }
+
+    /// Puts the binding in one slot of the action, a slot past the end adds it. Refuses bindings
+    /// another action already uses where both can fire at once: the players share a keyboard but
+    /// each has their own gamepad, and the in-game menu actions share everything with play.
+    fn rebind(
+        &mut self,
+        target: RebindTarget,
+        gamepad: Option<Gamepad>,
+        slot: usize,
+        binding: Binding,
+    ) -> Result<(), RebindTarget> {
+        let replaced = self.bindings(target, gamepad).get(slot).copied();
+        match target {
+            RebindTarget::Player(action) => {
+                if let Some(conflict) = key_conflict(&self.player_two, binding) {
+                    return Err(RebindTarget::PlayerTwo(conflict));
+                }
+                if let Some(conflict) = self.in_game_menu_conflict(binding) {
+                    return Err(RebindTarget::Menu(conflict));
+                }
+                self.rebind_player(PlayerNumber(0), gamepad, action, replaced, binding)
+                    .map_err(RebindTarget::Player)
+            }
+            RebindTarget::PlayerTwo(action) => {
+                if let Some(conflict) = key_conflict(&self.player, binding) {
+                    return Err(RebindTarget::Player(conflict));
+                }
+                if let Some(conflict) = self.in_game_menu_conflict(binding) {
+                    return Err(RebindTarget::Menu(conflict));
+                }
+                self.rebind_player(PlayerNumber(1), gamepad, action, replaced, binding)
+                    .map_err(RebindTarget::PlayerTwo)
+            }
+            RebindTarget::Menu(action) => {
+                if IN_GAME_MENU_ACTIONS.contains(&action) {
+                    if let Some(conflict) = bound_action(&self.player, binding) {
+                        return Err(RebindTarget::Player(conflict));
+                    }
+                    if let Some(conflict) = bound_action(&self.player_two, binding) {
+                        return Err(RebindTarget::PlayerTwo(conflict));
+                    }
+                }
+                rebind_action(&mut self.menu, action, replaced, binding).map_err(RebindTarget::Menu)
+            }
+        }
+    }
+
+    /// Keys always go to the player. Gamepad bindings go to the gamepad's profile, which starts as
+    /// a copy of the player's gamepad bindings the first time one is changed.
+    fn rebind_player(
+        &mut self,
+        player: PlayerNumber,
+        gamepad: Option<Gamepad>,
+        action: PlayerAction,
+        replaced: Option<Binding>,
+        binding: Binding,
+    ) -> Result<(), PlayerAction> {
+        let player_bindings = match player.0 {
+            0 => &mut self.player,
+            _ => &mut self.player_two,
+        };
+
+        let bindings = match gamepad {
+            Some(gamepad) if !binding.is_key() => {
+                self.gamepad_profiles.entry(gamepad.id).or_insert_with(|| {
+                    player_bindings
+                        .iter()
+                        .map(|(action, action_bindings)| {
+                            let buttons = action_bindings.iter().filter(|v| !v.is_key());
+                            (*action, buttons.copied().collect())
+                        })
+                        .collect()
+                })
+            }
+            _ => player_bindings,
+        };
+
+        rebind_action(bindings, action, replaced, binding)
+    }
+
+    fn in_game_menu_conflict(&self, binding: Binding) -> Option<PauseMenuAction> {
+        IN_GAME_MENU_ACTIONS.into_iter().find(|action| {
+            self.menu
+                .get(action)
+                .map_or(false, |action_bindings| action_bindings.contains(&binding))
+        })
+    }
+}
+
+fn input_map<A: Actionlike>(
+    bindings: &HashMap<A, Vec<Binding>>,
+    gamepad: Option<Gamepad>,
+) -> InputMap<A> {
+    let mut input_map = InputMap::default();
+    for (action, action_bindings) in bindings {
+        // Without a gamepad of its own the map would pick up someone else's.
+        for binding in action_bindings
+            .iter()
+            .filter(|v| gamepad.is_some() || v.is_key())
+        {
+            input_map.insert(*binding, action.clone());
+        }
+    }
+    if let Some(gamepad) = gamepad {
+        input_map.set_gamepad(gamepad);
+    }
+    input_map
+}
+
+fn bound_action<A: Copy>(bindings: &HashMap<A, Vec<Binding>>, binding: Binding) -> Option<A> {
+    bindings
+        .iter()
+        .find(|(_, action_bindings)| action_bindings.contains(&binding))
+        .map(|(action, _)| *action)
+}
+
+fn key_conflict(
+    bindings: &HashMap<PlayerAction, Vec<Binding>>,
+    binding: Binding,
+) -> Option<PlayerAction> {
+    if !binding.is_key() {
+        return None;
+    }
+
+    bound_action(bindings, binding)
+}
+
+/// Swaps the replaced binding for the new one, adding it when the replaced one isn't in this map.
+fn rebind_action<A: Copy + Eq + Hash>(
+    bindings: &mut HashMap<A, Vec<Binding>>,
+    action: A,
+    replaced: Option<Binding>,
+    binding: Binding,
+) -> Result<(), A> {
+    if let Some((conflict, _)) = bindings
+        .iter()
+        .find(|(other, other_bindings)| **other != action && other_bindings.contains(&binding))
+    {
+        return Err(*conflict);
+    }
+
+    let action_bindings = bindings.entry(action).or_default();
+    if action_bindings.contains(&binding) {
+        return Ok(());
+    }
+
+    match replaced.and_then(|replaced| action_bindings.iter_mut().find(|v| **v == replaced)) {
+        Some(existing) => *existing = binding,
+        None => action_bindings.push(binding),
+    }
+
+    Ok(())
+}
+
+/// Replaces hard coded input maps with the configured ones when spawned, rebound or a gamepad is plugged in.
+fn apply_player_controls(
+    controls: Res<Controls>,
+    gamepads: Res<ConnectedGamepads>,
+    coop: Res<CoopMode>,
+    mut input_query: Query<(
+        &mut InputMap<PlayerAction>,
+        &PlayerNumber,
+        ChangeTrackers<InputMap<PlayerAction>>,
+    )>,
+) {
+    for (mut input_map, player, trackers) in &mut input_query {
+        if controls.is_changed()
+            || gamepads.is_changed()
+            || coop.is_changed()
+            || trackers.is_added()
+        {
+            let gamepad = gamepads.player_gamepad(*player, coop.0);
+            *input_map = controls.player_input_map(*player, coop.0, gamepad);
+        }
+    }
+}
+
+/// The first gamepad plugged in drives the menus.
+fn apply_menu_controls(
+    controls: Res<Controls>,
+    gamepads: Res<ConnectedGamepads>,
+    mut input_query: Query<(
+        &mut InputMap<PauseMenuAction>,
+        ChangeTrackers<InputMap<PauseMenuAction>>,
+    )>,
+) {
+    for (mut input_map, trackers) in &mut input_query {
+        if controls.is_changed() || gamepads.is_changed() || trackers.is_added() {
+            *input_map = controls.menu_input_map(gamepads.0.first().copied());
+        }
+    }
+}
+
+/// Gamepads in the order they were plugged in.
+#[derive(Default)]
+pub struct ConnectedGamepads(pub Vec<Gamepad>);
+
+impl ConnectedGamepads {
+    /// Alone the first gamepad is used, in co-op each player takes one in the order they connected.
+    pub fn player_gamepad(&self, player: PlayerNumber, coop: bool) -> Option<Gamepad> {
+        self.0.get(if coop { player.0 } else { 0 }).copied()
+    }
+}
+
+fn track_gamepads(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut connected_gamepads: ResMut<ConnectedGamepads>,
+) {
+    for GamepadEvent(gamepad, event_type) in gamepad_events.iter() {
+        match event_type {
+            GamepadEventType::Connected => {
+                info!("Gamepad {} connected", gamepad.id);
+                connected_gamepads.0.push(*gamepad);
+            }
+            GamepadEventType::Disconnected => {
+                info!("Gamepad {} disconnected", gamepad.id);
+                connected_gamepads.0.retain(|v| v != gamepad);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum RebindTarget {
+    Player(PlayerAction),
+    PlayerTwo(PlayerAction),
+    Menu(PauseMenuAction),
+}
+
+impl RebindTarget {
+    fn player(&self) -> Option<PlayerNumber> {
+        match self {
+            RebindTarget::Player(_) => Some(PlayerNumber(0)),
+            RebindTarget::PlayerTwo(_) => Some(PlayerNumber(1)),
+            RebindTarget::Menu(_) => None,
+        }
+    }
+
+    /// The gamepad whose profile the target's gamepad bindings are shown from and saved to, the
+    /// one the player takes in co-op. Menus share one set of bindings.
+    fn gamepad(&self, gamepads: &ConnectedGamepads) -> Option<Gamepad> {
+        self.player()
+            .and_then(|player| gamepads.player_gamepad(player, true))
+    }
+
+    fn all() -> Vec<Self> {
+        PlayerAction::variants()
+            .map(RebindTarget::Player)
+            .chain(PlayerAction::variants().map(RebindTarget::PlayerTwo))
+            .chain(PauseMenuAction::variants().map(RebindTarget::Menu))
+            .collect()
+    }
+
+    fn label(&self) -> String {
+        match self {
+            RebindTarget::Player(action) => format!("P1 {action:?}"),
+            RebindTarget::PlayerTwo(action) => format!("P2 {action:?}"),
+            RebindTarget::Menu(action) => format!("Menu {action:?}"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Rebinding {
+    selected: usize,
+    /// Which of the selected action's bindings is replaced, one past the last adds a new one. A
+    /// key replacing a gamepad binding, or the other way round, is added instead.
+    slot: usize,
+    capturing: bool,
+    message: Option<String>,
+}
+
+#[derive(Component, Default)]
+struct ControlsMenu;
+
+#[derive(Component)]
+struct ControlsRow(usize);
+
+#[derive(Component)]
+struct ControlsMessage;
+
+fn spawn_controls_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    mut rebinding: ResMut<Rebinding>,
+) {
+    *rebinding = Rebinding::default();
+
+    let text_style = TextStyle {
+        font: font_assets.game.clone(),
+        font_size: 18.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_self: AlignSelf::Center,
+                margin: UiRect {
+                    top: Val::Px(0.0),
+                    left: Val::Auto,
+                    bottom: Val::Px(0.0),
+                    right: Val::Auto,
+                },
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            color: Color::BLACK.into(),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::from_section(
+                    "Controls".to_string(),
+                    TextStyle {
+                        font_size: 40.0,
+                        ..text_style.clone()
+                    },
+                ),
+                ..default()
+            });
+
+            for index in 0..RebindTarget::all().len() {
+                parent
+                    .spawn_bundle(TextBundle {
+                        text: Text::from_section(String::new(), text_style.clone()),
+                        ..default()
+                    })
+                    .insert(ControlsRow(index));
+            }
+
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::from_section(
+                        String::new(),
+                        TextStyle {
+                            color: Color::ORANGE_RED,
+                            ..text_style.clone()
+                        },
+                    ),
+                    ..default()
+                })
+                .insert(ControlsMessage);
+
+            parent.spawn_bundle(TextBundle {
+                text: Text::from_section(
+                    "Up/Down select, Left/Right pick a binding, Enter/A rebind it, Esc/B back"
+                        .to_string(),
+                    text_style.clone(),
+                ),
+                ..default()
+            });
+        })
+        .insert(ControlsMenu);
+}
+
+/// Navigation here is hard coded so a bad binding can never lock the player out of fixing it.
+fn rebind_controls(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<ConnectedGamepads>,
+    mut rebinding: ResMut<Rebinding>,
+    mut controls: ResMut<Controls>,
+) {
+    let targets = RebindTarget::all();
+
+    if rebinding.capturing {
+        if keys.just_pressed(KeyCode::Back) {
+            rebinding.capturing = false;
+            return;
+        }
+
+        let captured = keys
+            .get_just_pressed()
+            .next()
+            .map(|key| Binding::Key(*key))
+            .or_else(|| {
+                buttons
+                    .get_just_pressed()
+                    .next()
+                    .map(|button| Binding::GamepadButton(button.button_type))
+            })
+            .or_else(|| {
+                gamepads.0.iter().find_map(|gamepad| {
+                    STICK_AXES.into_iter().find_map(|axis_type| {
+                        let value = axes.get(GamepadAxis::new(*gamepad, axis_type))?;
+                        if value > STICK_CAPTURE_THRESHOLD {
+                            Some(Binding::GamepadAxis(axis_type, AxisDirection::Positive))
+                        } else if value < -STICK_CAPTURE_THRESHOLD {
+                            Some(Binding::GamepadAxis(axis_type, AxisDirection::Negative))
+                        } else {
+                            None
+                        }
+                    })
+                })
+            });
+
+        if let Some(binding) = captured {
+            let target = targets[rebinding.selected];
+            let slot = rebinding.slot;
+            rebinding.capturing = false;
+            rebinding.message = controls
+                .rebind(target, target.gamepad(&gamepads), slot, binding)
+                .err()
+                .map(|conflict| format!("{binding} is already used by {}", conflict.label()));
+        }
+        return;
+    }
+
+    let button_pressed = |button_type| {
+        gamepads
+            .0
+            .iter()
+            .any(|gamepad| buttons.just_pressed(GamepadButton::new(*gamepad, button_type)))
+    };
+
+    if keys.just_pressed(KeyCode::Up) || button_pressed(GamepadButtonType::DPadUp) {
+        rebinding.selected = (rebinding.selected + targets.len() - 1) % targets.len();
+        rebinding.slot = 0;
+    }
+    if keys.just_pressed(KeyCode::Down) || button_pressed(GamepadButtonType::DPadDown) {
+        rebinding.selected = (rebinding.selected + 1) % targets.len();
+        rebinding.slot = 0;
+    }
+
+    let target = targets[rebinding.selected];
+    let slots = controls.bindings(target, target.gamepad(&gamepads)).len() + 1;
+    if keys.just_pressed(KeyCode::Left) || button_pressed(GamepadButtonType::DPadLeft) {
+        rebinding.slot = (rebinding.slot + slots - 1) % slots;
+    }
+    if keys.just_pressed(KeyCode::Right) || button_pressed(GamepadButtonType::DPadRight) {
+        rebinding.slot = (rebinding.slot + 1) % slots;
+    }
+
+    if keys.just_pressed(KeyCode::Return) || button_pressed(GamepadButtonType::South) {
+        rebinding.capturing = true;
+        rebinding.message = None;
+    }
+    if keys.just_pressed(KeyCode::Escape) || button_pressed(GamepadButtonType::East) {
+        commands.insert_resource(NextState(PauseState::Paused));
+    }
+}
+
+fn update_controls_menu(
+    mut row_query: Query<(&mut Text, &ControlsRow), Without<ControlsMessage>>,
+    mut message_query: Query<&mut Text, With<ControlsMessage>>,
+    rebinding: Res<Rebinding>,
+    controls: Res<Controls>,
+    gamepads: Res<ConnectedGamepads>,
+) {
+    if !rebinding.is_changed() && !controls.is_changed() && !gamepads.is_changed() {
+        return;
+    }
+
+    let targets = RebindTarget::all();
+
+    for (mut text, ControlsRow(index)) in &mut row_query {
+        let target = targets[*index];
+        let is_selected = *index == rebinding.selected;
+        let gamepad = target.gamepad(&gamepads);
+
+        let bindings = if is_selected && rebinding.capturing {
+            "press a key or button, Backspace to cancel".to_string()
+        } else if is_selected {
+            controls
+                .bindings(target, gamepad)
+                .iter()
+                .map(Binding::to_string)
+                .chain(["+".to_string()])
+                .enumerate()
+                .map(|(slot, binding)| {
+                    if slot == rebinding.slot {
+                        format!("[{binding}]")
+                    } else {
+                        binding
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            controls
+                .bindings(target, gamepad)
+                .iter()
+                .map(Binding::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let label = match gamepad {
+            Some(gamepad) => format!("{} (pad {})", target.label(), gamepad.id),
+            None => target.label(),
+        };
+        text.sections[0].value = format!("{label}: {bindings}");
+        text.sections[0].style.color = if is_selected {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
+    }
+
+    for mut text in &mut message_query {
+        text.sections[0].value = rebinding.message.clone().unwrap_or_default();
+    }
+}
+
+fn save_controls(controls: Res<Controls>) {
+    controls.write();
+}
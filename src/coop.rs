@@ -0,0 +1,129 @@
+use super::*;
+
+pub struct CoopPlugin;
+
+impl Plugin for CoopPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CoopMode>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Paused)
+                    .with_system(toggle_coop)
+                    .with_system(update_coop_text)
+                    .into(),
+            )
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(regroup_players)
+                    .into(),
+            );
+    }
+}
+
+/// Whether a second player is playing on the same machine.
+#[derive(Default)]
+pub struct CoopMode(pub bool);
+
+#[derive(Component)]
+pub struct CoopText;
+
+fn toggle_coop(
+    action_query: Query<&ActionState<PauseMenuAction>>,
+    mut coop: ResMut<CoopMode>,
+    ui_audio: Res<AudioChannel<UiChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for action in &action_query {
+        if action.just_pressed(PauseMenuAction::ToggleCoop) {
+            coop.0 = !coop.0;
+            ui_audio.play(audio_assets.ui_select.clone());
+        }
+    }
+}
+
+fn update_coop_text(mut text_query: Query<&mut Text, With<CoopText>>, coop: Res<CoopMode>) {
+    for mut text in &mut text_query {
+        text.sections[0].value = format!("Co-op: {}", if coop.0 { "On" } else { "Off" });
+    }
+}
+
+/// Finds a spot next to `anchor` where a player fits without overlapping a wall, falling back
+/// to the anchor itself.
+pub fn partner_position(
+    rapier_context: &RapierContext,
+    anchor: Vec3,
+    player: PlayerNumber,
+) -> Vec3 {
+    let offset = player.spawn_offset();
+    [
+        offset,
+        -offset,
+        Vec3::new(offset.y, offset.x, 0.0),
+        Vec3::new(-offset.y, -offset.x, 0.0),
+    ]
+    .into_iter()
+    .map(|offset| anchor + offset)
+    .find(|candidate| {
+        rapier_context
+            .intersection_with_shape(
+                candidate.truncate(),
+                0.0,
+                &Collider::cuboid(PLAYER_HALF_EXTENTS.x, PLAYER_HALF_EXTENTS.y),
+                QueryFilter::only_fixed().exclude_sensors(),
+            )
+            .is_none()
+    })
+    .unwrap_or(anchor)
+}
+
+/// Brings a player left behind into the room the other walked into, since they have to share
+/// the screen. Waits for the new level to spawn if it isn't yet.
+fn regroup_players(
+    mut player_query: Query<(&mut Transform, &PlayerNumber), With<Player>>,
+    level_query: Query<(&Handle<LdtkLevel>, &Transform), Without<Player>>,
+    level_selection: Res<LevelSelection>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    rapier_context: Res<RapierContext>,
+    mut regrouped_level: Local<Option<String>>,
+) {
+    for (level_handle, level_transform) in &level_query {
+        let level = match ldtk_levels.get(level_handle) {
+            Some(ldtk_level) if level_selection.is_match(&0, &ldtk_level.level) => {
+                &ldtk_level.level
+            }
+            _ => continue,
+        };
+
+        if regrouped_level.as_ref() == Some(&level.iid) {
+            return;
+        }
+
+        let level_min = level_transform.translation.truncate();
+        let level_max = level_min + Vec2::new(level.px_wid as f32, level.px_hei as f32);
+        let in_level = |translation: Vec3| {
+            let position = translation.truncate();
+            position.cmpgt(level_min).all() && position.cmplt(level_max).all()
+        };
+
+        let (arrival, arrived) = match player_query
+            .iter()
+            .find(|(transform, _)| in_level(transform.translation))
+        {
+            Some((transform, number)) => (transform.translation, *number),
+            None => return,
+        };
+
+        for (mut transform, number) in &mut player_query {
+            if !in_level(transform.translation) {
+                let offset = PlayerNumber(number.0.abs_diff(arrived.0));
+                transform.translation = partner_position(&rapier_context, arrival, offset);
+            }
+        }
+
+        *regrouped_level = Some(level.iid.clone());
+        return;
+    }
+}
@@ -0,0 +1,144 @@
+use bevy::{
+    ui::UiScale,
+    window::{WindowMode, WindowResized},
+};
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InputManagerPlugin::<DisplayAction>::default())
+            .insert_resource(ClearColor(Color::BLACK))
+            .add_startup_system(spawn_display_detector)
+            .add_system(toggle_fullscreen)
+            .add_system(remember_window_size)
+            .add_system(scale_ui);
+    }
+}
+
+/// The height and width the UI was laid out for.
+const UI_DESIGN_SIZE: f32 = 512.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    Fullscreen,
+}
+
+impl DisplayMode {
+    fn window_mode(&self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Fullscreen => WindowMode::BorderlessFullscreen,
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub mode: DisplayMode,
+    /// Size of the window when not fullscreen.
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            mode: DisplayMode::Windowed,
+            width: 512.0,
+            height: 512.0,
+        }
+    }
+}
+
+impl DisplaySettings {
+    pub fn window_descriptor(&self) -> WindowDescriptor {
+        WindowDescriptor {
+            width: self.width,
+            height: self.height,
+            mode: self.mode.window_mode(),
+            resizable: true,
+            ..default()
+        }
+    }
+}
+
+#[derive(Actionlike, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum DisplayAction {
+    ToggleFullscreen,
+}
+
+fn spawn_display_detector(mut commands: Commands) {
+    commands.spawn_bundle(InputManagerBundle {
+        input_map: InputMap::new([(KeyCode::F11, DisplayAction::ToggleFullscreen)]),
+        action_state: ActionState::default(),
+    });
+}
+
+fn toggle_fullscreen(
+    action_query: Query<&ActionState<DisplayAction>>,
+    mut windows: ResMut<Windows>,
+    mut save_data: ResMut<SaveData>,
+) {
+    for action_state in &action_query {
+        if action_state.just_pressed(DisplayAction::ToggleFullscreen) {
+            save_data.display.mode = match save_data.display.mode {
+                DisplayMode::Windowed => DisplayMode::Fullscreen,
+                DisplayMode::Fullscreen => DisplayMode::Windowed,
+            };
+
+            if let Some(window) = windows.get_primary_mut() {
+                window.set_mode(save_data.display.mode.window_mode());
+            }
+
+            save_data.write();
+        }
+    }
+}
+
+/// How long the window has to keep its size before the save is written, so dragging a window
+/// edge doesn't write the file every frame.
+const RESIZE_SAVE_DELAY: f32 = 0.5;
+
+/// Keeps the windowed size in the save so the next run opens at the same size.
+fn remember_window_size(
+    mut resize_events: EventReader<WindowResized>,
+    windows: Res<Windows>,
+    mut save_data: ResMut<SaveData>,
+    mut pending_write: Local<Option<Timer>>,
+    time: Res<Time>,
+) {
+    for event in resize_events.iter() {
+        let is_primary = windows
+            .get_primary()
+            .map_or(false, |window| window.id() == event.id);
+
+        if is_primary && save_data.display.mode == DisplayMode::Windowed {
+            save_data.display.width = event.width;
+            save_data.display.height = event.height;
+            *pending_write = Some(Timer::from_seconds(RESIZE_SAVE_DELAY, false));
+        }
+    }
+
+    if let Some(timer) = pending_write.as_mut() {
+        if timer.tick(time.delta()).finished() {
+            save_data.write();
+            *pending_write = None;
+        }
+    }
+}
+
+fn scale_ui(windows: Res<Windows>, mut ui_scale: ResMut<UiScale>) {
+    if let Some(window) = windows.get_primary() {
+        let scale = (window.width().min(window.height()) / UI_DESIGN_SIZE) as f64;
+        if ui_scale.scale != scale {
+            ui_scale.scale = scale;
+        }
+    }
+}
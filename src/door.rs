@@ -8,7 +8,7 @@ impl Plugin for DoorPlugin {
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(GameState::Game)
-                    .run_not_in_state(PauseState::Paused)
+                    .run_in_state(PauseState::Unpaused)
                     .with_system(opening_door)
                     .with_system(track_door_switches)
                     .with_system(link_door_inputs)
@@ -30,6 +30,10 @@ struct DoorBundle {
     #[from_entity_instance]
     instance: EntityInstance,
 
+    #[from_entity_instance]
+    passable: PassableBy,
+
+    #[from_entity_instance]
     #[bundle]
     collision: DoorCollision,
 
@@ -39,12 +43,25 @@ struct DoorBundle {
 }
 
 #[derive(Component, Default)]
-struct DoorActivationControl {
+pub struct DoorActivationControl {
     switches: Vec<Entity>,
     control: DoorControl,
     pressed: Vec<Entity>,
 }
 
+impl DoorActivationControl {
+    pub fn is_active(&self) -> bool {
+        match self.control {
+            DoorControl::Or => !self.pressed.is_empty(),
+            DoorControl::And => self.pressed.len() == self.switches.len(),
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        !self.switches.is_empty()
+    }
+}
+
 #[derive(Default)]
 enum DoorControl {
     #[default]
@@ -76,6 +93,7 @@ impl From<EntityInstance> for DoorActivationControl {
 struct DoorCollision {
     collider: Collider,
     rigid_body: RigidBody,
+    collision_groups: CollisionGroups,
 }
 
 impl Default for DoorCollision {
@@ -83,36 +101,71 @@ impl Default for DoorCollision {
         Self {
             collider: Collider::cuboid(12.0, 12.0),
             rigid_body: RigidBody::Fixed,
+            collision_groups: CollisionGroups::new(DOOR_GROUP, ALL_GROUPS),
+        }
+    }
+}
+
+impl From<EntityInstance> for DoorCollision {
+    fn from(entity_instance: EntityInstance) -> Self {
+        Self {
+            collision_groups: PassableBy::from(entity_instance).collision_groups(DOOR_GROUP),
+            ..default()
         }
     }
 }
 
 fn opening_door(
     mut door_query: Query<
-        (&mut TextureAtlasSprite, &DoorActivationControl, Entity),
+        (
+            &mut TextureAtlasSprite,
+            &DoorActivationControl,
+            &PassableBy,
+            &GlobalTransform,
+            Entity,
+        ),
         (With<Door>, Changed<DoorActivationControl>),
     >,
     mut commands: Commands,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+    mut focus_event: EventWriter<CameraFocusEvent>,
+    mut sfx_event: EventWriter<SfxEvent>,
 ) {
-    for (mut sprite, activators, entity) in &mut door_query {
-        if (matches!(activators.control, DoorControl::Or) && activators.pressed.len() > 0)
-            || (matches!(activators.control, DoorControl::And)
-                && activators.pressed.len() == activators.switches.len())
-        {
+    for (mut sprite, activators, passable, door_transform, entity) in &mut door_query {
+        let position = Some(door_transform.translation().truncate());
+
+        if activators.is_active() {
+            if sprite.index != 3 {
+                focus_event.send(CameraFocusEvent {
+                    target: entity,
+                    duration: 1.0,
+                });
+                sfx_event.send(SfxEvent(SoundEffect::DoorOpen, position));
+            }
+
             sprite.index = 3;
             commands.entity(entity).remove_bundle::<DoorCollision>();
         } else {
+            if sprite.index == 3 {
+                shake_event.send(CameraShakeEvent(0.4));
+                sfx_event.send(SfxEvent(SoundEffect::DoorClose, position));
+            }
+
             sprite.index = 0;
-            commands
-                .entity(entity)
-                .insert_bundle(DoorCollision::default());
+            commands.entity(entity).insert_bundle(DoorCollision {
+                collision_groups: passable.collision_groups(DOOR_GROUP),
+                ..default()
+            });
         }
     }
 }
 
 fn link_door_inputs(
-    mut door_query: Query<(&mut DoorActivationControl, &EntityInstance), Added<Door>>,
-    switch_query: Query<(Entity, &EntityInstance), Added<Switch>>,
+    mut door_query: Query<
+        (&mut DoorActivationControl, &EntityInstance),
+        Added<DoorActivationControl>,
+    >,
+    switch_query: Query<(Entity, &EntityInstance), Or<(Added<Switch>, Added<LaserReceiver>)>>,
 ) {
     let switches = switch_query.iter().collect::<Vec<_>>();
     for (mut activation_control, door_instance) in &mut door_query {
@@ -138,7 +191,7 @@ fn link_door_inputs(
 }
 
 fn track_door_switches(
-    mut door_query: Query<&mut DoorActivationControl, With<Door>>,
+    mut door_query: Query<&mut DoorActivationControl>,
     mut pressed_event: EventReader<SwitchPressedEvent>,
 ) {
     for SwitchPressedEvent(button_entity, state) in pressed_event.iter() {
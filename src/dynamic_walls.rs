@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+
+pub struct DynamicWallsPlugin;
+
+impl Plugin for DynamicWallsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<RetractableWallBundle>("RetractableWall")
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(retract_walls)
+                    .with_system(crumble_floors)
+                    .into(),
+            );
+    }
+}
+
+/// Clears the `Collision` cells under it while its switches are active.
+#[derive(Component, Default)]
+pub struct RetractableWall {
+    retracted: HashMap<GridCoords, i32>,
+}
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct RetractableWallBundle {
+    retractable: RetractableWall,
+
+    #[from_entity_instance]
+    switches: DoorActivationControl,
+
+    #[from_entity_instance]
+    instance: EntityInstance,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+fn collision_layer(ldtk_level: &LdtkLevel) -> &LayerInstance {
+    ldtk_level
+        .level
+        .layer_instances
+        .as_ref()
+        .expect("Level asset should have layers")
+        .iter()
+        .find(|v| v.identifier == "Collision")
+        .expect("Level should have a Collision layer")
+}
+
+fn retract_walls(
+    mut retractable_query: Query<
+        (
+            &mut RetractableWall,
+            &mut Visibility,
+            &DoorActivationControl,
+            &EntityInstance,
+            &Parent,
+        ),
+        Changed<DoorActivationControl>,
+    >,
+    mut cell_query: Query<(&GridCoords, &mut IntGridCell, &Parent), With<Wall>>,
+    parent_query: Query<&Parent, (Without<Wall>, Without<RetractableWall>)>,
+    level_query: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    behaviours: Res<CollisionBehaviours>,
+) {
+    for (mut retractable, mut visibility, activators, entity_instance, parent) in
+        &mut retractable_query
+    {
+        let level_entity = match parent_query.get(parent.get()) {
+            Ok(level_entity) => level_entity.get(),
+            Err(_) => continue,
+        };
+
+        if activators.is_active() && retractable.retracted.is_empty() {
+            let layer = collision_layer(
+                ldtk_levels
+                    .get(
+                        level_query
+                            .get(level_entity)
+                            .expect("Layer should be a child of a level"),
+                    )
+                    .expect("Level should be loaded by this point"),
+            );
+
+            let grid_size = layer.grid_size as f32;
+            let size = IVec2::new(entity_instance.width, entity_instance.height).as_vec2();
+            let top_left = entity_instance.px.as_vec2() - entity_instance.pivot * size;
+            let min = (top_left / grid_size).floor().as_ivec2();
+            let max = ((top_left + size) / grid_size).ceil().as_ivec2() - IVec2::ONE;
+
+            for (coords, mut cell, cell_parent) in &mut cell_query {
+                // LDtk counts rows from the top, grid coords count them from the bottom.
+                let ldtk_y = layer.c_hei - 1 - coords.y;
+                let in_area =
+                    (min.x..=max.x).contains(&coords.x) && (min.y..=max.y).contains(&ldtk_y);
+
+                // Hazards under the wall stay put, only the wall itself retracts.
+                if in_area
+                    && cell.value != 0
+                    && behaviours
+                        .values
+                        .get(&cell.value)
+                        .map_or(true, CollisionBehaviour::is_wall)
+                    && parent_query
+                        .get(cell_parent.get())
+                        .map_or(false, |v| v.get() == level_entity)
+                {
+                    retractable.retracted.insert(*coords, cell.value);
+                    cell.value = 0;
+                }
+            }
+
+            visibility.is_visible = false;
+        } else if !activators.is_active() && !retractable.retracted.is_empty() {
+            for (coords, mut cell, cell_parent) in &mut cell_query {
+                if let Some(&value) = retractable.retracted.get(coords) {
+                    if parent_query
+                        .get(cell_parent.get())
+                        .map_or(false, |v| v.get() == level_entity)
+                    {
+                        cell.value = value;
+                    }
+                }
+            }
+
+            retractable.retracted.clear();
+            visibility.is_visible = true;
+        }
+    }
+}
+
+/// Turns crumbling floor cells into walls once the player has stepped off them.
+fn crumble_floors(
+    mut cell_query: Query<(Entity, &GridCoords, &mut IntGridCell, &Parent), With<Wall>>,
+    parent_query: Query<&Parent, Without<Wall>>,
+    level_query: Query<(&GlobalTransform, &Handle<LdtkLevel>)>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    behaviours: Res<CollisionBehaviours>,
+    mut stepped_on: Local<HashSet<Entity>>,
+) {
+    let (crumbling_value, solid_value) = match (behaviours.crumbling_value, behaviours.solid_value)
+    {
+        (Some(crumbling_value), Some(solid_value)) => (crumbling_value, solid_value),
+        _ => return,
+    };
+
+    for (entity, coords, mut cell, parent) in &mut cell_query {
+        if cell.value != crumbling_value {
+            continue;
+        }
+
+        if let Some((level_transform, ldtk_level)) = parent_query
+            .get(parent.get())
+            .ok()
+            .and_then(|level_entity| level_query.get(level_entity.get()).ok())
+            .and_then(|(level_transform, level_handle)| {
+                Some((level_transform, ldtk_levels.get(level_handle)?))
+            })
+        {
+            let grid_size = collision_layer(ldtk_level).grid_size as f32;
+            let centre = level_transform.translation().truncate()
+                + (IVec2::new(coords.x, coords.y).as_vec2() + 0.5) * grid_size;
+
+            let occupied = player_query.iter().any(|player_transform| {
+                (player_transform.translation().truncate() - centre)
+                    .abs()
+                    .max_element()
+                    < grid_size / 2.0
+            });
+
+            if occupied {
+                stepped_on.insert(entity);
+            } else if stepped_on.remove(&entity) {
+                cell.value = solid_value;
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+
+use super::*;
+
+pub struct HazardPlugin;
+
+impl Plugin for HazardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HazardSettings>()
+            .init_resource::<ActiveCheckpoint>()
+            .add_event::<PlayerDeathEvent>()
+            .register_ldtk_entity::<CheckpointBundle>("Checkpoint")
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(spawn_hazard_sensors)
+                    .with_system(hazard_contacts)
+                    .with_system(reach_checkpoint)
+                    .with_system(kill_player)
+                    .with_system(respawn_player)
+                    .into(),
+            );
+    }
+}
+
+pub struct HazardSettings {
+    /// Whether a box pushed into a pit fills it, making the pit safe to walk over.
+    pub pits_fillable: bool,
+}
+
+impl Default for HazardSettings {
+    fn default() -> Self {
+        Self {
+            pits_fillable: true,
+        }
+    }
+}
+
+/// Sent with the player that died.
+pub struct PlayerDeathEvent(pub Entity);
+
+/// A `Collision` cell that kills the player, picked by the value's identifier in
+/// [`CollisionBehaviours`].
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Hazard {
+    Pit,
+    Spikes,
+    Lava,
+}
+
+#[derive(Component, Default)]
+struct HazardSensor;
+
+#[derive(Component, Default)]
+pub struct Checkpoint;
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct CheckpointBundle {
+    checkpoint: Checkpoint,
+
+    #[bundle]
+    collider: CheckpointCollider,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+#[derive(Bundle)]
+struct CheckpointCollider {
+    collider: Collider,
+    event: ActiveEvents,
+    sensor: Sensor,
+    collision_groups: CollisionGroups,
+}
+
+impl Default for CheckpointCollider {
+    fn default() -> Self {
+        Self {
+            collider: Collider::cuboid(8.0, 8.0),
+            event: ActiveEvents::COLLISION_EVENTS,
+            sensor: Sensor,
+            collision_groups: CollisionGroups::new(SENSOR_GROUP, PLAYER_GROUP),
+        }
+    }
+}
+
+/// Where the player returns to after dying, if they have touched a checkpoint in this room.
+#[derive(Default)]
+pub struct ActiveCheckpoint(pub Option<Vec3>);
+
+/// Marks a player playing the death effect before being respawned.
+#[derive(Component)]
+pub struct Dying(Timer);
+
+impl Default for Dying {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.6, false))
+    }
+}
+
+#[derive(Component, Default)]
+pub struct FilledPit;
+
+fn spawn_hazard_sensors(
+    mut commands: Commands,
+    cell_query: Query<(&GridCoords, &IntGridCell, &Parent), Added<IntGridCell>>,
+    parent_query: Query<&Parent, Without<IntGridCell>>,
+    level_query: Query<&Handle<LdtkLevel>>,
+    levels: Res<Assets<LdtkLevel>>,
+    behaviours: Res<CollisionBehaviours>,
+) {
+    for (grid_coords, int_grid_cell, parent) in &cell_query {
+        let hazard = match behaviours.values.get(&int_grid_cell.value) {
+            Some(&CollisionBehaviour::Hazard(hazard)) => hazard,
+            _ => continue,
+        };
+
+        if let Ok(level_entity) = parent_query.get(parent.get()) {
+            let grid_size = levels
+                .get(
+                    level_query
+                        .get(level_entity.get())
+                        .expect("Layer should be a child of a level"),
+                )
+                .expect("Level should be loaded by this point")
+                .level
+                .layer_instances
+                .as_ref()
+                .expect("Level asset should have layers")
+                .iter()
+                .find(|v| v.identifier == "Collision")
+                .expect("Level should have a Collision layer")
+                .grid_size as f32;
+
+            // Sensors are inset so that only bodies mostly over a hazard are affected.
+            commands
+                .entity(level_entity.get())
+                .with_children(|builder| {
+                    builder
+                        .spawn()
+                        .insert_bundle(TransformBundle::from(Transform::from_xyz(
+                            (grid_coords.x as f32 + 0.5) * grid_size,
+                            (grid_coords.y as f32 + 0.5) * grid_size,
+                            0.0,
+                        )))
+                        .insert(Collider::cuboid(grid_size / 4.0, grid_size / 4.0))
+                        .insert(Sensor)
+                        .insert(ActiveEvents::COLLISION_EVENTS)
+                        .insert(CollisionGroups::new(HAZARD_GROUP, PLAYER_GROUP | BOX_GROUP))
+                        .insert(HazardSensor)
+                        .insert(hazard);
+                });
+        }
+    }
+}
+
+fn hazard_contacts(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    sensor_query: Query<(&Hazard, &Transform), With<HazardSensor>>,
+    player_query: Query<(), (With<Player>, Without<Dying>)>,
+    mut box_query: Query<&mut Transform, (With<Box>, Without<FilledPit>, Without<HazardSensor>)>,
+    settings: Res<HazardSettings>,
+    mut death_event: EventWriter<PlayerDeathEvent>,
+) {
+    let mut filled = Vec::new();
+
+    for collision in collision_events.iter() {
+        if let CollisionEvent::Started(object_1, object_2, _) = collision {
+            for (sensor, body) in [(*object_1, *object_2), (*object_2, *object_1)] {
+                if filled.contains(&sensor) || filled.contains(&body) {
+                    continue;
+                }
+
+                if let Ok((hazard, sensor_transform)) = sensor_query.get(sensor) {
+                    if player_query.contains(body) {
+                        death_event.send(PlayerDeathEvent(body));
+                    } else if let Ok(mut box_transform) = box_query.get_mut(body) {
+                        if *hazard == Hazard::Pit && settings.pits_fillable {
+                            box_transform.translation.x = sensor_transform.translation.x;
+                            box_transform.translation.y = sensor_transform.translation.y;
+                            commands
+                                .entity(body)
+                                .remove::<RigidBody>()
+                                .remove::<Collider>()
+                                .insert(FilledPit);
+                            commands.entity(sensor).despawn_recursive();
+                            filled.push(sensor);
+                            filled.push(body);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn reach_checkpoint(
+    mut collision_events: EventReader<CollisionEvent>,
+    checkpoint_query: Query<&GlobalTransform, With<Checkpoint>>,
+    player_query: Query<(), With<Player>>,
+    mut active_checkpoint: ResMut<ActiveCheckpoint>,
+    level_selection: Res<LevelSelection>,
+) {
+    // A checkpoint only counts in its own room, entering another one respawns at its entrance.
+    if level_selection.is_changed() {
+        active_checkpoint.0 = None;
+    }
+
+    for collision in collision_events.iter() {
+        if let CollisionEvent::Started(object_1, object_2, _) = collision {
+            for (checkpoint, body) in [(object_1, object_2), (object_2, object_1)] {
+                if let Ok(checkpoint_transform) = checkpoint_query.get(*checkpoint) {
+                    if player_query.contains(*body) {
+                        active_checkpoint.0 = Some(checkpoint_transform.translation());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn kill_player(
+    mut commands: Commands,
+    mut death_event: EventReader<PlayerDeathEvent>,
+    mut player_query: Query<&mut Velocity, (With<Player>, Without<Dying>)>,
+    mut shake_event: EventWriter<CameraShakeEvent>,
+    mut zoom_event: EventWriter<CameraZoomEvent>,
+) {
+    let dying = death_event
+        .iter()
+        .map(|PlayerDeathEvent(player)| *player)
+        .collect::<HashSet<_>>();
+
+    for entity in dying {
+        let mut velocity = match player_query.get_mut(entity) {
+            Ok(velocity) => velocity,
+            Err(_) => continue,
+        };
+
+        *velocity = Velocity::zero();
+        commands.entity(entity).insert(Dying::default());
+
+        shake_event.send(CameraShakeEvent(0.6));
+        zoom_event.send(CameraZoomEvent {
+            zoom: 1.25,
+            duration: 0.4,
+        });
+    }
+}
+
+fn respawn_player(
+    mut commands: Commands,
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Dying,
+            &mut Transform,
+            &mut Velocity,
+            &mut PlayerAbilityState,
+            &PlayerNumber,
+        ),
+        With<Player>,
+    >,
+    active_checkpoint: Res<ActiveCheckpoint>,
+    room_entry: Res<RoomEntry>,
+    rapier_context: Res<RapierContext>,
+    time: Res<Time>,
+) {
+    for (entity, mut dying, mut transform, mut velocity, mut ability_state, number) in
+        &mut player_query
+    {
+        dying.0.tick(time.delta());
+        *velocity = Velocity::zero();
+        transform.scale = Vec3::splat(1.0 - dying.0.percent()).max(Vec3::splat(0.05));
+
+        if dying.0.finished() {
+            if let Some(anchor) = active_checkpoint.0.or(room_entry.translation) {
+                let translation = partner_position(&rapier_context, anchor, *number);
+                transform.translation.x = translation.x;
+                transform.translation.y = translation.y;
+            }
+            transform.scale = Vec3::ONE;
+            // Ending the ability also ends the echo, which hands its keys back to the player.
+            *ability_state = PlayerAbilityState::Idle;
+
+            commands.entity(entity).remove::<Dying>();
+        }
+    }
+}
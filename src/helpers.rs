@@ -5,3 +5,27 @@ pub fn despawn_entities_with<T: Component>(mut commands: Commands, query: Query<
         commands.entity(entity).despawn_recursive();
     }
 }
+
+pub fn ldtk_entity_layer<'a>(
+    ldtk_level: &'a LdtkLevel,
+    entity_instance: &EntityInstance,
+) -> Option<&'a LayerInstance> {
+    ldtk_level
+        .level
+        .layer_instances
+        .as_ref()?
+        .iter()
+        .find(|layer| {
+            layer
+                .entity_instances
+                .iter()
+                .any(|v| v.iid == entity_instance.iid)
+        })
+}
+
+pub fn ldtk_grid_to_translation(grid: IVec2, layer: &LayerInstance) -> Vec2 {
+    Vec2::new(
+        (grid.x as f32 + 0.5) * layer.grid_size as f32,
+        (layer.c_hei as f32 - grid.y as f32 - 0.5) * layer.grid_size as f32,
+    )
+}
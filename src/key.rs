@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+pub struct KeyPlugin;
+
+impl Plugin for KeyPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<KeyBundle>("Key")
+            .register_ldtk_entity::<LockedDoorBundle>("LockedDoor")
+            .add_enter_system(GameState::Game, spawn_inventory_hud)
+            .add_exit_system(GameState::Game, despawn_entities_with::<InventoryHud>)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(remove_used_keys_and_doors)
+                    .with_system(pick_up_keys)
+                    .with_system(unlock_doors)
+                    .with_system(update_inventory_hud)
+                    .into(),
+            );
+    }
+}
+
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum KeyColour {
+    #[default]
+    Red,
+    Green,
+    Blue,
+    Yellow,
+}
+
+impl KeyColour {
+    fn color(&self) -> Color {
+        match self {
+            KeyColour::Red => Color::RED,
+            KeyColour::Green => Color::GREEN,
+            KeyColour::Blue => Color::BLUE,
+            KeyColour::Yellow => Color::YELLOW,
+        }
+    }
+}
+
+impl From<EntityInstance> for KeyColour {
+    fn from(entity_instance: EntityInstance) -> Self {
+        match &entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "Colour")
+            .expect("Key and locked door entities must have a colour field")
+            .value
+        {
+            FieldValue::Enum(Some(v)) if v == "Red" => KeyColour::Red,
+            FieldValue::Enum(Some(v)) if v == "Green" => KeyColour::Green,
+            FieldValue::Enum(Some(v)) if v == "Blue" => KeyColour::Blue,
+            FieldValue::Enum(Some(v)) if v == "Yellow" => KeyColour::Yellow,
+            _ => panic!("Key and locked door colour field must be an enum"),
+        }
+    }
+}
+
+/// Keys held by a player or an echo.
+#[derive(Component, Default, Clone)]
+pub struct Inventory {
+    pub keys: Vec<KeyColour>,
+}
+
+#[derive(Component, Default)]
+pub struct Key;
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct KeyBundle {
+    key: Key,
+
+    #[from_entity_instance]
+    colour: KeyColour,
+
+    #[from_entity_instance]
+    instance: EntityInstance,
+
+    #[bundle]
+    collider: KeyCollider,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+#[derive(Bundle)]
+struct KeyCollider {
+    collider: Collider,
+    event: ActiveEvents,
+    sensor: Sensor,
+    collision_groups: CollisionGroups,
+}
+
+impl Default for KeyCollider {
+    fn default() -> Self {
+        Self {
+            collider: Collider::cuboid(5.0, 5.0),
+            event: ActiveEvents::COLLISION_EVENTS,
+            sensor: Sensor,
+            collision_groups: CollisionGroups::new(SENSOR_GROUP, PLAYER_GROUP | ECHO_GROUP),
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct LockedDoor;
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct LockedDoorBundle {
+    door: LockedDoor,
+
+    #[from_entity_instance]
+    colour: KeyColour,
+
+    #[from_entity_instance]
+    instance: EntityInstance,
+
+    #[from_entity_instance]
+    #[bundle]
+    collision: LockedDoorCollision,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+#[derive(Bundle)]
+struct LockedDoorCollision {
+    collider: Collider,
+    rigid_body: RigidBody,
+    event: ActiveEvents,
+    collision_groups: CollisionGroups,
+}
+
+impl Default for LockedDoorCollision {
+    fn default() -> Self {
+        Self {
+            collider: Collider::cuboid(12.0, 12.0),
+            rigid_body: RigidBody::Fixed,
+            event: ActiveEvents::COLLISION_EVENTS,
+            collision_groups: CollisionGroups::new(DOOR_GROUP, ALL_GROUPS),
+        }
+    }
+}
+
+impl From<EntityInstance> for LockedDoorCollision {
+    fn from(entity_instance: EntityInstance) -> Self {
+        Self {
+            collision_groups: PassableBy::from(entity_instance).collision_groups(DOOR_GROUP),
+            ..default()
+        }
+    }
+}
+
+/// Levels spawn every key and locked door again, so the ones already used in a saved room go.
+fn remove_used_keys_and_doors(
+    mut commands: Commands,
+    key_query: Query<(Entity, &EntityInstance), (With<Key>, Added<EntityInstance>)>,
+    door_query: Query<(Entity, &EntityInstance), (With<LockedDoor>, Added<EntityInstance>)>,
+    save_data: Res<SaveData>,
+) {
+    for (entity, entity_instance) in &key_query {
+        if save_data.collected_keys.contains(&entity_instance.iid) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    for (entity, entity_instance) in &door_query {
+        if save_data.unlocked_doors.contains(&entity_instance.iid) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn pick_up_keys(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    key_query: Query<(&KeyColour, &EntityInstance), With<Key>>,
+    mut carrier_query: Query<&mut Inventory>,
+    mut room_entry: ResMut<RoomEntry>,
+) {
+    let mut collected = Vec::new();
+
+    for collision in collision_events.iter() {
+        if let CollisionEvent::Started(object_1, object_2, _) = collision {
+            for (key, carrier) in [(object_1, object_2), (object_2, object_1)] {
+                if collected.contains(key) {
+                    continue;
+                }
+
+                if let (Ok((colour, entity_instance)), Ok(mut inventory)) =
+                    (key_query.get(*key), carrier_query.get_mut(*carrier))
+                {
+                    inventory.keys.push(*colour);
+                    room_entry.collected_keys.push(entity_instance.iid.clone());
+                    commands.entity(*key).despawn_recursive();
+                    collected.push(*key);
+                }
+            }
+        }
+    }
+}
+
+fn unlock_doors(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    door_query: Query<(&KeyColour, &EntityInstance), With<LockedDoor>>,
+    mut carrier_query: Query<&mut Inventory>,
+    mut room_entry: ResMut<RoomEntry>,
+) {
+    let mut unlocked = Vec::new();
+
+    for collision in collision_events.iter() {
+        if let CollisionEvent::Started(object_1, object_2, _) = collision {
+            for (door, carrier) in [(object_1, object_2), (object_2, object_1)] {
+                if unlocked.contains(door) {
+                    continue;
+                }
+
+                if let (Ok((colour, entity_instance)), Ok(mut inventory)) =
+                    (door_query.get(*door), carrier_query.get_mut(*carrier))
+                {
+                    if let Some(index) = inventory.keys.iter().position(|key| key == colour) {
+                        inventory.keys.remove(index);
+                        room_entry.unlocked_doors.push(entity_instance.iid.clone());
+                        commands.entity(*door).despawn_recursive();
+                        unlocked.push(*door);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Component, Default)]
+struct InventoryHud;
+
+fn spawn_inventory_hud(mut commands: Commands) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..default()
+                },
+                // Rows are listed top down, bevy's UI counts upwards.
+                flex_direction: FlexDirection::ColumnReverse,
+                ..default()
+            },
+            color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(InventoryHud);
+}
+
+/// Shows a row of keys for each player, marked with the player's tint in co-op.
+fn update_inventory_hud(
+    mut commands: Commands,
+    hud_query: Query<Entity, With<InventoryHud>>,
+    player_query: Query<(&Inventory, &PlayerNumber), With<Player>>,
+    changed_query: Query<(), (With<Player>, Changed<Inventory>)>,
+    removed_players: RemovedComponents<Player>,
+) {
+    if changed_query.is_empty() && removed_players.iter().next().is_none() {
+        return;
+    }
+
+    let mut players = player_query.iter().collect::<Vec<_>>();
+    players.sort_by_key(|(_, number)| number.0);
+    let coop = players.len() > 1;
+
+    for hud in &hud_query {
+        commands
+            .entity(hud)
+            .despawn_descendants()
+            .with_children(|parent| {
+                for (inventory, number) in &players {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Row,
+                                ..default()
+                            },
+                            color: Color::NONE.into(),
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            if coop {
+                                row.spawn_bundle(NodeBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Px(4.0), Val::Px(12.0)),
+                                        margin: UiRect::all(Val::Px(2.0)),
+                                        ..default()
+                                    },
+                                    color: number.tint().into(),
+                                    ..default()
+                                });
+                            }
+
+                            for key in &inventory.keys {
+                                row.spawn_bundle(NodeBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Px(12.0), Val::Px(12.0)),
+                                        margin: UiRect::all(Val::Px(2.0)),
+                                        ..default()
+                                    },
+                                    color: key.color().into(),
+                                    ..default()
+                                });
+                            }
+                        });
+                }
+            });
+    }
+}
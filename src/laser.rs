@@ -0,0 +1,307 @@
+use super::*;
+
+pub struct LaserPlugin;
+
+impl Plugin for LaserPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<LaserEmitterBundle>("LaserEmitter")
+            .register_ldtk_entity::<LaserReceiverBundle>("LaserReceiver")
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(cast_lasers)
+                    .into(),
+            );
+    }
+}
+
+const LASER_RANGE: f32 = 1024.0;
+const LASER_MAX_BOUNCES: usize = 16;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+enum LaserDirection {
+    #[default]
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl LaserDirection {
+    fn vector(&self) -> Vec2 {
+        match self {
+            LaserDirection::Up => Vec2::Y,
+            LaserDirection::Down => Vec2::NEG_Y,
+            LaserDirection::Left => Vec2::NEG_X,
+            LaserDirection::Right => Vec2::X,
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct LaserEmitter {
+    direction: LaserDirection,
+    lethal: bool,
+}
+
+impl From<EntityInstance> for LaserEmitter {
+    fn from(entity_instance: EntityInstance) -> Self {
+        let direction = match &entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "Direction")
+            .expect("Laser emitter entity must have a direction field")
+            .value
+        {
+            FieldValue::Enum(Some(v)) if v == "Up" => LaserDirection::Up,
+            FieldValue::Enum(Some(v)) if v == "Down" => LaserDirection::Down,
+            FieldValue::Enum(Some(v)) if v == "Left" => LaserDirection::Left,
+            FieldValue::Enum(Some(v)) if v == "Right" => LaserDirection::Right,
+            _ => panic!("Laser emitter direction field must be an enum"),
+        };
+
+        let lethal = match &entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "Lethal")
+            .expect("Laser emitter entity must have a lethal field")
+            .value
+        {
+            FieldValue::Bool(lethal) => *lethal,
+            _ => panic!("Laser emitter lethal field must be a bool"),
+        };
+
+        Self { direction, lethal }
+    }
+}
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct LaserEmitterBundle {
+    #[from_entity_instance]
+    emitter: LaserEmitter,
+
+    #[bundle]
+    collision: LaserBlockCollision,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+#[derive(Component, Default)]
+pub struct LaserReceiver {
+    powered: bool,
+}
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct LaserReceiverBundle {
+    receiver: LaserReceiver,
+
+    #[from_entity_instance]
+    instance: EntityInstance,
+
+    #[bundle]
+    collision: LaserBlockCollision,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+#[derive(Bundle)]
+struct LaserBlockCollision {
+    collider: Collider,
+    rigid_body: RigidBody,
+    collision_groups: CollisionGroups,
+}
+
+impl Default for LaserBlockCollision {
+    fn default() -> Self {
+        Self {
+            collider: Collider::cuboid(8.0, 8.0),
+            rigid_body: RigidBody::Fixed,
+            collision_groups: CollisionGroups::new(WALL_GROUP, ALL_GROUPS),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+enum MirrorOrientation {
+    /// Faces like `/`, turning a beam heading right upwards.
+    #[default]
+    Slash,
+    /// Faces like `\`, turning a beam heading right downwards.
+    Backslash,
+}
+
+#[derive(Component, Default)]
+pub struct Mirror {
+    orientation: MirrorOrientation,
+}
+
+impl Mirror {
+    fn reflect(&self, direction: LaserDirection) -> LaserDirection {
+        use LaserDirection::*;
+
+        match (self.orientation, direction) {
+            (MirrorOrientation::Slash, Right) => Up,
+            (MirrorOrientation::Slash, Up) => Right,
+            (MirrorOrientation::Slash, Left) => Down,
+            (MirrorOrientation::Slash, Down) => Left,
+            (MirrorOrientation::Backslash, Right) => Down,
+            (MirrorOrientation::Backslash, Down) => Right,
+            (MirrorOrientation::Backslash, Left) => Up,
+            (MirrorOrientation::Backslash, Up) => Left,
+        }
+    }
+}
+
+impl From<EntityInstance> for Mirror {
+    fn from(entity_instance: EntityInstance) -> Self {
+        Self {
+            orientation: match &entity_instance
+                .field_instances
+                .iter()
+                .find(|v| v.identifier == "Orientation")
+                .expect("Mirror box entity must have an orientation field")
+                .value
+            {
+                FieldValue::Enum(Some(v)) if v == "Slash" => MirrorOrientation::Slash,
+                FieldValue::Enum(Some(v)) if v == "Backslash" => MirrorOrientation::Backslash,
+                _ => panic!("Mirror box orientation field must be an enum"),
+            },
+        }
+    }
+}
+
+#[derive(Component)]
+struct LaserBeam;
+
+#[allow(clippy::too_many_arguments)]
+fn cast_lasers(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    emitter_query: Query<(Entity, &LaserEmitter, &GlobalTransform)>,
+    mirror_query: Query<(&Mirror, &GlobalTransform)>,
+    mut receiver_query: Query<(Entity, &mut LaserReceiver, &mut TextureAtlasSprite)>,
+    player_query: Query<(), With<Player>>,
+    mut beam_query: Query<(Entity, &mut Transform, &mut Sprite), With<LaserBeam>>,
+    mut pressed_event: EventWriter<SwitchPressedEvent>,
+    mut death_event: EventWriter<PlayerDeathEvent>,
+) {
+    let mut segments = Vec::new();
+    let mut powered = Vec::new();
+    let mut players_hit = Vec::new();
+
+    for (emitter_entity, emitter, emitter_transform) in &emitter_query {
+        let mut origin = emitter_transform.translation().truncate();
+        let mut direction = emitter.direction;
+        let mut ignored = emitter_entity;
+
+        for _ in 0..LASER_MAX_BOUNCES {
+            let hit = rapier_context.cast_ray(
+                origin,
+                direction.vector(),
+                LASER_RANGE,
+                true,
+                QueryFilter::new()
+                    .exclude_sensors()
+                    .exclude_collider(ignored),
+            );
+
+            let end = match hit {
+                Some((_, distance)) => origin + direction.vector() * distance,
+                None => origin + direction.vector() * LASER_RANGE,
+            };
+            segments.push((origin, end));
+
+            let hit_entity = match hit {
+                Some((hit_entity, _)) => hit_entity,
+                None => break,
+            };
+
+            if let Ok((mirror, mirror_transform)) = mirror_query.get(hit_entity) {
+                let centre = mirror_transform.translation().truncate();
+                segments.push((end, centre));
+                origin = centre;
+                direction = mirror.reflect(direction);
+                ignored = hit_entity;
+                continue;
+            }
+
+            if receiver_query.contains(hit_entity) {
+                powered.push(hit_entity);
+            } else if emitter.lethal && player_query.contains(hit_entity) {
+                players_hit.push(hit_entity);
+            }
+            break;
+        }
+    }
+
+    for (entity, mut receiver, mut sprite) in &mut receiver_query {
+        let is_powered = powered.contains(&entity);
+        if receiver.powered != is_powered {
+            receiver.powered = is_powered;
+            sprite.index = if is_powered { 1 } else { 0 };
+            pressed_event.send(SwitchPressedEvent(
+                entity,
+                if is_powered {
+                    SwitchState::Pressed
+                } else {
+                    SwitchState::Released
+                },
+            ));
+        }
+    }
+
+    for player in players_hit {
+        death_event.send(PlayerDeathEvent(player));
+    }
+
+    // Beam sprites are reused from frame to frame, only the number of segments spawns or
+    // despawns any.
+    let mut segments = segments
+        .into_iter()
+        .filter(|(start, end)| start != end)
+        .map(|(start, end)| beam_placement(start, end));
+    for (beam, mut transform, mut sprite) in &mut beam_query {
+        match segments.next() {
+            Some((translation, size)) => {
+                if transform.translation != translation {
+                    transform.translation = translation;
+                }
+                if sprite.custom_size != Some(size) {
+                    sprite.custom_size = Some(size);
+                }
+            }
+            None => commands.entity(beam).despawn_recursive(),
+        }
+    }
+
+    for (translation, size) in segments {
+        commands
+            .spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(1.0, 0.1, 0.1, 0.8),
+                    custom_size: Some(size),
+                    ..default()
+                },
+                transform: Transform::from_translation(translation),
+                ..default()
+            })
+            .insert(LaserBeam);
+    }
+}
+
+/// Where a beam segment's sprite goes and how big it is.
+fn beam_placement(start: Vec2, end: Vec2) -> (Vec3, Vec2) {
+    let length = start.distance(end);
+    let size = if start.x == end.x {
+        Vec2::new(2.0, length)
+    } else {
+        Vec2::new(length, 2.0)
+    };
+
+    (((start + end) / 2.0).extend(50.0), size)
+}
@@ -1,14 +1,23 @@
+use std::collections::HashMap;
+
 use super::*;
 
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_enter_system(GameState::Game, spawn_level)
-            .add_system(
-                update_level_selection
+        app.init_resource::<RoomEntry>()
+            .add_event::<RoomRestartEvent>()
+            .add_enter_system(GameState::Game, spawn_level)
+            .add_system_set(
+                ConditionSet::new()
                     .run_in_state(GameState::Game)
-                    .run_not_in_state(PauseState::Paused),
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(update_level_selection)
+                    .with_system(track_room_entry)
+                    .with_system(request_room_restart)
+                    .with_system(restart_room)
+                    .into(),
             );
     }
 }
@@ -22,19 +31,20 @@ fn spawn_level(mut commands: Commands, level: Res<LevelsAsset>) {
 
 fn update_level_selection(
     level_query: Query<(&Handle<LdtkLevel>, &Transform), Without<Player>>,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<&GlobalTransform, With<Player>>,
     mut level_selection: ResMut<LevelSelection>,
     ldtk_levels: Res<Assets<LdtkLevel>>,
 ) {
     for (level_handle, level_transform) in &level_query {
         if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
             for player_transform in &player_query {
-                if player_transform.translation.x
+                let player_translation = player_transform.translation();
+                if player_translation.x
                     < level_transform.translation.x + ldtk_level.level.px_wid as f32
-                    && player_transform.translation.x > level_transform.translation.x
-                    && player_transform.translation.y
+                    && player_translation.x > level_transform.translation.x
+                    && player_translation.y
                         < level_transform.translation.y + ldtk_level.level.px_hei as f32
-                    && player_transform.translation.y > level_transform.translation.y
+                    && player_translation.y > level_transform.translation.y
                     && !level_selection.is_match(&0, &ldtk_level.level)
                 {
                     *level_selection = LevelSelection::Iid(ldtk_level.level.iid.clone());
@@ -43,3 +53,125 @@ fn update_level_selection(
         }
     }
 }
+
+pub struct RoomRestartEvent;
+
+/// The state the players were in when they entered the current room, restored on restart.
+#[derive(Default)]
+pub struct RoomEntry {
+    /// Where the first player entered, the others are placed around them.
+    pub translation: Option<Vec3>,
+    inventories: HashMap<PlayerNumber, Inventory>,
+    /// Iids of keys picked up in this room, saved once the room is left and dropped on restart.
+    pub collected_keys: Vec<String>,
+    /// Iids of locked doors opened in this room, saved like `collected_keys`.
+    pub unlocked_doors: Vec<String>,
+}
+
+fn track_room_entry(
+    mut player_query: Query<
+        (
+            &Transform,
+            &mut Inventory,
+            &PlayerNumber,
+            ChangeTrackers<Player>,
+        ),
+        With<Player>,
+    >,
+    level_selection: Res<LevelSelection>,
+    mut room_entry: ResMut<RoomEntry>,
+    mut save_data: ResMut<SaveData>,
+) {
+    if level_selection.is_changed() {
+        let RoomEntry {
+            collected_keys,
+            unlocked_doors,
+            ..
+        } = &mut *room_entry;
+        save_data.collected_keys.append(collected_keys);
+        save_data.unlocked_doors.append(unlocked_doors);
+    }
+
+    for (transform, mut inventory, number, player_tracker) in &mut player_query {
+        if player_tracker.is_added() {
+            inventory.keys = save_data.inventory_mut(*number).clone();
+        } else if !level_selection.is_changed() {
+            continue;
+        }
+
+        // A co-op partner drops in and out, so rooms are entered where the first player was.
+        if number.0 == 0 {
+            room_entry.translation = Some(transform.translation);
+        }
+        room_entry.inventories.insert(*number, inventory.clone());
+
+        *save_data.inventory_mut(*number) = inventory.keys.clone();
+        save_data.write();
+    }
+}
+
+fn request_room_restart(
+    action_query: Query<&ActionState<PlayerAction>, With<Player>>,
+    mut restart_event: EventWriter<RoomRestartEvent>,
+) {
+    for action_state in &action_query {
+        if action_state.just_pressed(PlayerAction::Restart) {
+            restart_event.send(RoomRestartEvent);
+        }
+    }
+}
+
+fn restart_room(
+    mut commands: Commands,
+    mut restart_event: EventReader<RoomRestartEvent>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            &mut Inventory,
+            &mut PlayerAbilityState,
+            &PlayerNumber,
+        ),
+        With<Player>,
+    >,
+    past_player_query: Query<Entity, With<PastPlayer>>,
+    level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+    level_selection: Res<LevelSelection>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    mut room_entry: ResMut<RoomEntry>,
+    rapier_context: Res<RapierContext>,
+) {
+    if restart_event.iter().count() == 0 {
+        return;
+    }
+
+    // The level respawns with the keys and locked doors used since entering it.
+    room_entry.collected_keys.clear();
+    room_entry.unlocked_doors.clear();
+
+    for (level_entity, level_handle) in &level_query {
+        if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
+            if level_selection.is_match(&0, &ldtk_level.level) {
+                commands.entity(level_entity).insert(Respawn);
+            }
+        }
+    }
+
+    for past_player_entity in &past_player_query {
+        commands.entity(past_player_entity).despawn_recursive();
+    }
+
+    for (mut transform, mut velocity, mut inventory, mut ability_state, number) in &mut player_query
+    {
+        if let Some(anchor) = room_entry.translation {
+            transform.translation = partner_position(&rapier_context, anchor, *number);
+        }
+        *velocity = Velocity::zero();
+        *inventory = room_entry
+            .inventories
+            .get(number)
+            .cloned()
+            .unwrap_or_default();
+        *ability_state = PlayerAbilityState::Idle;
+    }
+}
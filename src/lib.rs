@@ -7,15 +7,36 @@ pub use state::*;
 mod camera;
 pub use camera::*;
 
+mod camera_effects;
+pub use camera_effects::*;
+
+mod display;
+pub use display::*;
+
+mod animation;
+pub use animation::*;
+
 mod level;
 pub use level::*;
 
 mod music;
 pub use music::*;
 
+mod audio_settings;
+pub use audio_settings::*;
+
+mod audio_pool;
+pub use audio_pool::*;
+
+mod sfx;
+pub use sfx::*;
+
 mod player;
 pub use player::*;
 
+mod movement;
+pub use movement::*;
+
 mod walls;
 pub use walls::*;
 
@@ -28,12 +49,51 @@ pub use switch::*;
 mod door;
 pub use door::*;
 
+mod mover;
+pub use mover::*;
+
+mod teleporter;
+pub use teleporter::*;
+
+mod key;
+pub use key::*;
+
+mod save;
+pub use save::*;
+
+mod laser;
+pub use laser::*;
+
+mod hazard;
+pub use hazard::*;
+
+mod rect_merge;
+pub use rect_merge::*;
+
+mod collision_groups;
+pub use collision_groups::*;
+
+mod dynamic_walls;
+pub use dynamic_walls::*;
+
+mod surface;
+pub use surface::*;
+
+mod map;
+pub use map::*;
+
 mod helpers;
 pub use helpers::*;
 
 mod paused;
 pub use paused::*;
 
+mod controls;
+pub use controls::*;
+
+mod coop;
+pub use coop::*;
+
 pub use bevy_kira_audio::*;
 
 pub use bevy::prelude::*;
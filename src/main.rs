@@ -5,25 +5,38 @@ fn main() {
     App::new()
         .add_loopless_state(GameState::Loading)
         .add_loopless_state(PauseState::Unpaused)
-        .insert_resource(WindowDescriptor {
-            width: 512.0,
-            height: 512.0,
-            ..default()
-        })
+        .insert_resource(SaveData::load().display.window_descriptor())
         .insert_resource(ImageSettings::default_nearest())
         .add_plugins(DefaultPlugins)
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
+        .add_plugin(RapierPhysicsPlugin::<WallHookData>::pixels_per_meter(100.0))
         .add_plugin(LdtkPlugin)
         .add_plugin(AudioPlugin)
         .add_plugin(AssetPlugin)
         .add_plugin(CameraPlugin)
+        .add_plugin(CameraEffectsPlugin)
+        .add_plugin(DisplayPlugin)
+        .add_plugin(AnimationPlugin)
         .add_plugin(LevelPlugin)
+        .add_plugin(AudioSettingsPlugin)
         .add_plugin(MusicPlugin)
+        .add_plugin(SfxPlugin)
         .add_plugin(PlayerPlugin)
+        .add_plugin(MovementPlugin)
         .add_plugin(WallsPlugin)
+        .add_plugin(DynamicWallsPlugin)
+        .add_plugin(SurfacePlugin)
         .add_plugin(BoxPlugin)
         .add_plugin(SwitchPlugin)
         .add_plugin(DoorPlugin)
+        .add_plugin(MoverPlugin)
+        .add_plugin(TeleporterPlugin)
+        .add_plugin(KeyPlugin)
+        .add_plugin(LaserPlugin)
+        .add_plugin(HazardPlugin)
+        .add_plugin(SavePlugin)
+        .add_plugin(MapPlugin)
         .add_plugin(PausedPlugin)
+        .add_plugin(ControlsPlugin)
+        .add_plugin(CoopPlugin)
         .run();
 }
@@ -0,0 +1,223 @@
+use super::*;
+
+pub struct MapPlugin;
+
+impl Plugin for MapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(InputManagerPlugin::<MapAction>::default())
+            .add_startup_system(spawn_map_detector)
+            .add_enter_system(PauseState::Map, spawn_map)
+            .add_exit_system(PauseState::Map, despawn_entities_with::<MapScreen>)
+            .add_system(toggle_map.run_in_state(GameState::Game))
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(track_visited_rooms)
+                    .with_system(track_opened_doors)
+                    .into(),
+            );
+    }
+}
+
+/// The largest width and height the map is drawn at in UI pixels.
+const MAP_SIZE: f32 = 400.0;
+const DOOR_MARKER_SIZE: f32 = 6.0;
+
+#[derive(Actionlike, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MapAction {
+    Toggle,
+}
+
+#[derive(Component, Default)]
+struct MapScreen;
+
+fn spawn_map_detector(mut commands: Commands) {
+    commands.spawn_bundle(InputManagerBundle {
+        input_map: InputMap::new([(KeyCode::M, MapAction::Toggle)]),
+        action_state: ActionState::default(),
+    });
+}
+
+fn toggle_map(
+    mut commands: Commands,
+    action_query: Query<&ActionState<MapAction>>,
+    current_state: Res<CurrentState<PauseState>>,
+) {
+    for action_state in &action_query {
+        if action_state.just_pressed(MapAction::Toggle) {
+            match current_state.0 {
+                PauseState::Unpaused => commands.insert_resource(NextState(PauseState::Map)),
+                PauseState::Map => commands.insert_resource(NextState(PauseState::Unpaused)),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn track_visited_rooms(
+    level_selection: Res<LevelSelection>,
+    levels: Res<LevelsAsset>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut save_data: ResMut<SaveData>,
+) {
+    if !level_selection.is_changed() {
+        return;
+    }
+
+    if let Some(ldtk_asset) = ldtk_assets.get(&levels.map) {
+        for (index, level) in ldtk_asset.project.levels.iter().enumerate() {
+            if level_selection.is_match(&index, level)
+                && !save_data.visited_levels.contains(&level.iid)
+            {
+                save_data.visited_levels.push(level.iid.clone());
+                save_data.write();
+            }
+        }
+    }
+}
+
+fn track_opened_doors(
+    door_query: Query<(&DoorActivationControl, &EntityInstance), Changed<DoorActivationControl>>,
+    mut save_data: ResMut<SaveData>,
+) {
+    for (activators, entity_instance) in &door_query {
+        if activators.is_active() && !save_data.opened_doors.contains(&entity_instance.iid) {
+            save_data.opened_doors.push(entity_instance.iid.clone());
+            save_data.write();
+        }
+    }
+}
+
+fn spawn_map(
+    mut commands: Commands,
+    levels: Res<LevelsAsset>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    level_selection: Res<LevelSelection>,
+    save_data: Res<SaveData>,
+) {
+    let levels = &ldtk_assets
+        .get(&levels.map)
+        .expect("Levels should be loaded by this point")
+        .project
+        .levels;
+
+    // LDtk world coordinates have y pointing down, which is what `bottom` measures in the y up UI.
+    let min = levels
+        .iter()
+        .map(|level| IVec2::new(level.world_x, level.world_y))
+        .reduce(IVec2::min)
+        .unwrap_or_default()
+        .as_vec2();
+    let max = levels
+        .iter()
+        .map(|level| IVec2::new(level.world_x + level.px_wid, level.world_y + level.px_hei))
+        .reduce(IVec2::max)
+        .unwrap_or_default()
+        .as_vec2();
+    let scale = MAP_SIZE / (max - min).max_element().max(1.0);
+    let offset = (Vec2::splat(MAP_SIZE) - (max - min) * scale) / 2.0;
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.8).into(),
+            ..default()
+        })
+        .insert(MapScreen)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(MAP_SIZE), Val::Px(MAP_SIZE)),
+                        ..default()
+                    },
+                    color: Color::NONE.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (index, level) in levels.iter().enumerate() {
+                        if !save_data.visited_levels.contains(&level.iid) {
+                            continue;
+                        }
+
+                        let doors = level
+                            .layer_instances
+                            .iter()
+                            .flatten()
+                            .flat_map(|layer| &layer.entity_instances)
+                            .filter(|entity_instance| entity_instance.identifier == "Door")
+                            .collect::<Vec<_>>();
+
+                        // A room without doors has nothing to complete.
+                        let completed = !doors.is_empty()
+                            && doors
+                                .iter()
+                                .all(|door| save_data.opened_doors.contains(&door.iid));
+
+                        let color = if level_selection.is_match(&index, level) {
+                            Color::YELLOW
+                        } else if completed {
+                            Color::DARK_GREEN
+                        } else {
+                            Color::GRAY
+                        };
+
+                        let position = (IVec2::new(level.world_x, level.world_y).as_vec2() - min)
+                            * scale
+                            + offset;
+                        let size = IVec2::new(level.px_wid, level.px_hei).as_vec2() * scale;
+
+                        parent
+                            .spawn_bundle(NodeBundle {
+                                style: Style {
+                                    position_type: PositionType::Absolute,
+                                    position: UiRect {
+                                        left: Val::Px(position.x),
+                                        bottom: Val::Px(position.y),
+                                        ..default()
+                                    },
+                                    size: Size::new(Val::Px(size.x - 2.0), Val::Px(size.y - 2.0)),
+                                    ..default()
+                                },
+                                color: color.into(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                for door in doors {
+                                    let door_position = door.px.as_vec2() * scale
+                                        - Vec2::splat(DOOR_MARKER_SIZE / 2.0);
+
+                                    parent.spawn_bundle(NodeBundle {
+                                        style: Style {
+                                            position_type: PositionType::Absolute,
+                                            position: UiRect {
+                                                left: Val::Px(door_position.x),
+                                                bottom: Val::Px(door_position.y),
+                                                ..default()
+                                            },
+                                            size: Size::new(
+                                                Val::Px(DOOR_MARKER_SIZE),
+                                                Val::Px(DOOR_MARKER_SIZE),
+                                            ),
+                                            ..default()
+                                        },
+                                        color: if save_data.opened_doors.contains(&door.iid) {
+                                            Color::GREEN
+                                        } else {
+                                            Color::RED
+                                        }
+                                        .into(),
+                                        ..default()
+                                    });
+                                }
+                            });
+                    }
+                });
+        });
+}
@@ -0,0 +1,184 @@
+use super::*;
+
+pub struct MovementPlugin;
+
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MovementSettings>().add_system_set(
+            ConditionSet::new()
+                .run_in_state(GameState::Game)
+                .run_in_state(PauseState::Unpaused)
+                .with_system(player_movement)
+                .with_system(push_boxes)
+                .into(),
+        );
+    }
+}
+
+/// Tunables for how the player moves, speeds are in pixels per second.
+pub struct MovementSettings {
+    pub max_speed: f32,
+    /// How quickly the player speeds up towards the direction being held.
+    pub acceleration: f32,
+    /// How quickly the player slows down once nothing is held.
+    pub deceleration: f32,
+    /// The top speed while pushing a box.
+    pub push_speed: f32,
+    /// How quickly a pushed box speeds up, the push has to beat the box's own damping.
+    pub push_acceleration: f32,
+}
+
+impl Default for MovementSettings {
+    fn default() -> Self {
+        Self {
+            max_speed: 100.0,
+            acceleration: 1200.0,
+            deceleration: 1600.0,
+            push_speed: 60.0,
+            push_acceleration: 1500.0,
+        }
+    }
+}
+
+/// A box only counts as pushed while the player is moving mostly towards it.
+const PUSH_ALIGNMENT: f32 = 0.7;
+
+/// The held direction, scaled down by how far the sticks are pushed and never longer than 1.
+fn movement_input(action_state: &ActionState<PlayerAction>) -> Vec2 {
+    let strength = |action| {
+        if action_state.pressed(action) {
+            action_state.action_value(action).abs().min(1.0)
+        } else {
+            0.0
+        }
+    };
+
+    let input = Vec2::new(
+        strength(PlayerAction::Right) - strength(PlayerAction::Left),
+        strength(PlayerAction::Up) - strength(PlayerAction::Down),
+    );
+
+    if input.length_squared() > 1.0 {
+        input.normalize()
+    } else {
+        input
+    }
+}
+
+fn move_towards(current: Vec2, target: Vec2, max_delta: f32) -> Vec2 {
+    let difference = target - current;
+    if difference.length() <= max_delta {
+        target
+    } else {
+        current + difference.normalize() * max_delta
+    }
+}
+
+/// The box the player is walking into, if any.
+fn pushed_box(
+    player: Entity,
+    input: Vec2,
+    rapier_context: &RapierContext,
+    box_query: &Query<&GlobalTransform, With<Box>>,
+    player_transform: &GlobalTransform,
+) -> Option<Entity> {
+    if input == Vec2::ZERO {
+        return None;
+    }
+
+    rapier_context
+        .contacts_with(player)
+        .filter(|contact| contact.has_any_active_contacts())
+        .map(|contact| {
+            if contact.collider1() == player {
+                contact.collider2()
+            } else {
+                contact.collider1()
+            }
+        })
+        .find(|other| {
+            box_query.get(*other).map_or(false, |box_transform| {
+                let offset = (box_transform.translation() - player_transform.translation())
+                    .truncate()
+                    .normalize_or_zero();
+                offset.dot(input.normalize()) > PUSH_ALIGNMENT
+            })
+        })
+}
+
+fn player_movement(
+    mut player_query: Query<
+        (
+            Entity,
+            &mut Velocity,
+            &ActionState<PlayerAction>,
+            &CurrentSurface,
+            &GlobalTransform,
+        ),
+        With<Player>,
+    >,
+    box_query: Query<&GlobalTransform, With<Box>>,
+    rapier_context: Res<RapierContext>,
+    settings: Res<MovementSettings>,
+    time: Res<Time>,
+) {
+    for (entity, mut velocity, action_state, surface, transform) in &mut player_query {
+        let input = movement_input(action_state);
+
+        let pushing = pushed_box(entity, input, &rapier_context, &box_query, transform).is_some();
+        let max_speed = if pushing {
+            settings.push_speed
+        } else {
+            settings.max_speed
+        };
+
+        let target = input * max_speed * surface.0.speed_factor() + surface.0.drift();
+        let rate = if input == Vec2::ZERO {
+            settings.deceleration
+        } else {
+            settings.acceleration
+        };
+
+        velocity.linvel = move_towards(
+            velocity.linvel,
+            target,
+            rate * surface.0.traction() * time.delta_seconds(),
+        );
+    }
+}
+
+fn push_boxes(
+    player_query: Query<(Entity, &ActionState<PlayerAction>, &GlobalTransform), With<Player>>,
+    box_transform_query: Query<&GlobalTransform, With<Box>>,
+    mut box_query: Query<&mut Velocity, With<Box>>,
+    rapier_context: Res<RapierContext>,
+    settings: Res<MovementSettings>,
+    time: Res<Time>,
+) {
+    for (entity, action_state, transform) in &player_query {
+        let input = movement_input(action_state);
+
+        let pushed = match pushed_box(
+            entity,
+            input,
+            &rapier_context,
+            &box_transform_query,
+            transform,
+        ) {
+            Some(pushed) => pushed,
+            None => continue,
+        };
+
+        if let Ok(mut velocity) = box_query.get_mut(pushed) {
+            // Only speed the box up along the push, leaving any sideways motion to physics.
+            let direction = input.normalize();
+            let along = velocity.linvel.dot(direction);
+            let target = settings.push_speed * input.length();
+
+            if along < target {
+                let boost = (settings.push_acceleration * time.delta_seconds()).min(target - along);
+                velocity.linvel += direction * boost;
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use super::*;
+
+pub struct MoverPlugin;
+
+impl Plugin for MoverPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<MoverBundle>("Mover")
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(build_mover_path)
+                    .with_system(move_movers)
+                    .into(),
+            );
+    }
+}
+
+#[derive(Component, Default)]
+pub struct Mover;
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct MoverBundle {
+    mover: Mover,
+
+    #[from_entity_instance]
+    switches: DoorActivationControl,
+
+    #[from_entity_instance]
+    path: MoverPath,
+
+    #[from_entity_instance]
+    instance: EntityInstance,
+
+    #[from_entity_instance]
+    #[bundle]
+    collision: MoverCollision,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+#[derive(Component, Default)]
+struct MoverPath {
+    points: Vec<IVec2>,
+    waypoints: Vec<Vec3>,
+    current: usize,
+    speed: f32,
+}
+
+impl From<EntityInstance> for MoverPath {
+    fn from(entity_instance: EntityInstance) -> Self {
+        let points = match &entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "Path")
+            .expect("Mover entity must have a path field")
+            .value
+        {
+            FieldValue::Points(points) => points.iter().flatten().copied().collect(),
+            _ => panic!("Mover entity path field must be points"),
+        };
+
+        let speed = match &entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "Speed")
+            .expect("Mover entity must have a speed field")
+            .value
+        {
+            FieldValue::Float(Some(speed)) => *speed,
+            _ => panic!("Mover entity speed field must be a float"),
+        };
+
+        Self {
+            points,
+            waypoints: vec![],
+            current: 0,
+            speed,
+        }
+    }
+}
+
+#[derive(Bundle)]
+struct MoverCollision {
+    collider: Collider,
+    rigid_body: RigidBody,
+    velocity: Velocity,
+    collision_groups: CollisionGroups,
+}
+
+impl Default for MoverCollision {
+    fn default() -> Self {
+        Self {
+            collider: Collider::cuboid(8.0, 8.0),
+            rigid_body: RigidBody::KinematicVelocityBased,
+            velocity: Velocity::zero(),
+            collision_groups: CollisionGroups::new(WALL_GROUP, ALL_GROUPS),
+        }
+    }
+}
+
+impl From<EntityInstance> for MoverCollision {
+    fn from(entity_instance: EntityInstance) -> Self {
+        Self {
+            collider: Collider::cuboid(
+                entity_instance.width as f32 / 2.0,
+                entity_instance.height as f32 / 2.0,
+            ),
+            collision_groups: PassableBy::from(entity_instance).collision_groups(WALL_GROUP),
+            ..default()
+        }
+    }
+}
+
+fn build_mover_path(
+    mut mover_query: Query<(&mut MoverPath, &EntityInstance, &Transform, &Parent), Added<Mover>>,
+    parent_query: Query<&Parent>,
+    level_query: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+) {
+    for (mut path, entity_instance, transform, parent) in &mut mover_query {
+        let layer = parent_query
+            .get(parent.get())
+            .ok()
+            .and_then(|level_entity| level_query.get(level_entity.get()).ok())
+            .and_then(|level_handle| ldtk_levels.get(level_handle))
+            .and_then(|ldtk_level| ldtk_entity_layer(ldtk_level, entity_instance))
+            .expect("Mover entity should be spawned inside a loaded level");
+
+        let origin = ldtk_grid_to_translation(entity_instance.grid, layer);
+
+        let mut waypoints = vec![transform.translation];
+        for &point in &path.points {
+            let offset = ldtk_grid_to_translation(point, layer) - origin;
+            waypoints.push(transform.translation + offset.extend(0.0));
+        }
+
+        path.waypoints = waypoints;
+        path.current = 0;
+    }
+}
+
+fn move_movers(
+    mut mover_query: Query<
+        (
+            &mut MoverPath,
+            &mut Velocity,
+            &Transform,
+            &DoorActivationControl,
+        ),
+        With<Mover>,
+    >,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    if delta == 0.0 {
+        return;
+    }
+
+    for (mut path, mut velocity, transform, activators) in &mut mover_query {
+        if path.waypoints.is_empty() {
+            continue;
+        }
+
+        let destination = if activators.is_active() {
+            path.waypoints.len() - 1
+        } else {
+            0
+        };
+
+        let next = match path.current.cmp(&destination) {
+            std::cmp::Ordering::Less => path.current + 1,
+            std::cmp::Ordering::Greater => path.current - 1,
+            std::cmp::Ordering::Equal => path.current,
+        };
+
+        let offset = (path.waypoints[next] - transform.translation).truncate();
+
+        if offset.length() <= path.speed * delta {
+            velocity.linvel = offset / delta;
+            path.current = next;
+        } else {
+            velocity.linvel = offset.normalize() * path.speed;
+        }
+    }
+}
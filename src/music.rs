@@ -4,16 +4,231 @@ pub struct MusicPlugin;
 
 impl Plugin for MusicPlugin {
     fn build(&self, app: &mut App) {
-        app.add_enter_system(GameState::Game, start_background_music)
-            .add_exit_system(GameState::Game, stop_background_music);
+        app.add_channel_pool::<MusicSlot>(MUSIC_SLOTS)
+            .init_resource::<MusicTracks>()
+            .add_exit_system(GameState::Game, stop_background_music)
+            .add_system(select_level_music.run_in_state(GameState::Game))
+            .add_system(crossfade_music.run_in_state(GameState::Game))
+            .add_system(bend_music_during_ability.run_in_state(GameState::Game));
     }
 }
 
-fn start_background_music(audio_assets: Res<AudioAssets>, audio: Res<Audio>) {
-    audio.play_looped(audio_assets.music.clone());
-    audio.set_volume(0.3);
+const MUSIC_SLOTS: usize = 3;
+const CROSSFADE_TIME: f32 = 1.5;
+/// Played in levels without a `Music` field.
+const DEFAULT_TRACK: &str = "music";
+/// Music slows down and drops in pitch while the echo is being recorded.
+const ABILITY_PLAYBACK_RATE: f32 = 0.8;
+const ABILITY_BEND_IN_TIME: f32 = 0.2;
+const ABILITY_BEND_OUT_TIME: f32 = 1.0;
+
+/// Marks the channel pool music plays on. Each track plays on its own slot so it can fade
+/// independently and be paused in place.
+pub struct MusicSlot;
+
+type MusicSlots<'w, 's> = ChannelPool<'w, 's, MusicSlot>;
+
+fn play_track(slot: &dyn PooledChannel, track: Handle<AudioSource>) {
+    slot.stop();
+    slot.set_volume(0.0);
+    slot.play_looped(track);
+}
+
+#[derive(Default)]
+struct SlotState {
+    track: Option<String>,
+    /// How far faded in the slot is, from silent at 0 to full volume at 1.
+    fade: f32,
+    paused: bool,
+    last_used: f64,
+}
+
+pub struct MusicTracks {
+    slots: [SlotState; MUSIC_SLOTS],
+    current: Option<usize>,
+    applied_volumes: [Option<f32>; MUSIC_SLOTS],
+    playback_rate: f32,
+}
+
+impl Default for MusicTracks {
+    fn default() -> Self {
+        Self {
+            slots: default(),
+            current: None,
+            applied_volumes: default(),
+            playback_rate: 1.0,
+        }
+    }
+}
+
+fn level_track(level: &ldtk::Level) -> String {
+    level
+        .field_instances
+        .iter()
+        .find(|v| v.identifier == "Music")
+        .and_then(|field| match &field.value {
+            FieldValue::String(Some(track)) | FieldValue::Enum(Some(track)) => Some(track.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| DEFAULT_TRACK.to_string())
 }
 
-fn stop_background_music(audio: Res<Audio>) {
-    audio.stop();
+fn select_level_music(
+    mut tracks: ResMut<MusicTracks>,
+    slots: MusicSlots,
+    level_selection: Res<LevelSelection>,
+    levels: Res<LevelsAsset>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    audio_assets: Res<AudioAssets>,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    if !level_selection.is_changed() && tracks.current.is_some() {
+        return;
+    }
+
+    let track = match ldtk_assets.get(&levels.map).and_then(|ldtk_asset| {
+        ldtk_asset
+            .project
+            .levels
+            .iter()
+            .enumerate()
+            .find(|(index, level)| level_selection.is_match(index, level))
+    }) {
+        Some((_, level)) => level_track(level),
+        None => return,
+    };
+
+    let now = time.seconds_since_startup();
+
+    if let Some(current) = tracks.current {
+        if tracks.slots[current].track.as_ref() == Some(&track) {
+            return;
+        }
+    }
+
+    let index = match tracks
+        .slots
+        .iter()
+        .position(|slot| slot.track.as_ref() == Some(&track))
+    {
+        Some(index) => index,
+        None => {
+            // Reuse an empty slot, otherwise forget the track that was heard longest ago.
+            let current = tracks.current;
+            let index = (0..MUSIC_SLOTS)
+                .filter(|index| Some(*index) != current)
+                .min_by(|&a, &b| {
+                    let age = |index: usize| {
+                        let slot = &tracks.slots[index];
+                        slot.track.as_ref().map_or(f64::MIN, |_| slot.last_used)
+                    };
+                    age(a).total_cmp(&age(b))
+                })
+                .unwrap_or_default();
+
+            let handle = if track == DEFAULT_TRACK {
+                audio_assets.music.clone()
+            } else {
+                asset_server.load(&format!("audio/{track}.ogg"))
+            };
+            play_track(slots.channel(index), handle);
+
+            tracks.slots[index] = SlotState {
+                track: Some(track),
+                ..default()
+            };
+            tracks.applied_volumes[index] = Some(0.0);
+
+            index
+        }
+    };
+
+    if let Some(current) = tracks.current {
+        tracks.slots[current].last_used = now;
+    }
+    tracks.slots[index].last_used = now;
+    tracks.current = Some(index);
+}
+
+fn crossfade_music(
+    mut tracks: ResMut<MusicTracks>,
+    slots: MusicSlots,
+    save_data: Res<SaveData>,
+    time: Res<Time>,
+) {
+    let tracks = &mut *tracks;
+    let step = time.delta_seconds() / CROSSFADE_TIME;
+    let volume = MUSIC_VOLUME * save_data.audio.volume(AudioBus::Music);
+
+    for (index, state) in tracks.slots.iter_mut().enumerate() {
+        if state.track.is_none() {
+            continue;
+        }
+
+        let slot = slots.channel(index);
+
+        if tracks.current == Some(index) {
+            if state.paused {
+                slot.resume();
+                state.paused = false;
+            }
+            state.fade = (state.fade + step).min(1.0);
+        } else if !state.paused {
+            state.fade = (state.fade - step).max(0.0);
+
+            // Paused rather than stopped so coming back picks up where the track left off.
+            if state.fade == 0.0 {
+                slot.pause();
+                state.paused = true;
+            }
+        }
+
+        let slot_volume = state.fade * volume;
+        if tracks.applied_volumes[index] != Some(slot_volume) {
+            slot.set_volume(slot_volume);
+            tracks.applied_volumes[index] = Some(slot_volume);
+        }
+    }
+}
+
+fn bend_music_during_ability(
+    mut tracks: ResMut<MusicTracks>,
+    slots: MusicSlots,
+    player_query: Query<&PlayerAbilityState, With<Player>>,
+    time: Res<Time>,
+) {
+    let preforming = player_query
+        .iter()
+        .any(|ability_state| matches!(ability_state, PlayerAbilityState::Preforming));
+
+    let (target, fade_time) = if preforming {
+        (ABILITY_PLAYBACK_RATE, ABILITY_BEND_IN_TIME)
+    } else {
+        (1.0, ABILITY_BEND_OUT_TIME)
+    };
+
+    if tracks.playback_rate == target {
+        return;
+    }
+
+    let step = (1.0 - ABILITY_PLAYBACK_RATE) * time.delta_seconds() / fade_time;
+    tracks.playback_rate = if tracks.playback_rate < target {
+        (tracks.playback_rate + step).min(target)
+    } else {
+        (tracks.playback_rate - step).max(target)
+    };
+
+    for index in 0..MUSIC_SLOTS {
+        slots.channel(index).set_playback_rate(tracks.playback_rate);
+    }
+}
+
+fn stop_background_music(mut tracks: ResMut<MusicTracks>, slots: MusicSlots) {
+    for index in 0..MUSIC_SLOTS {
+        slots.channel(index).stop();
+        slots.channel(index).set_playback_rate(1.0);
+    }
+
+    *tracks = MusicTracks::default();
 }
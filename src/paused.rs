@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use super::*;
 
 pub struct PausedPlugin;
@@ -5,23 +7,54 @@ pub struct PausedPlugin;
 impl Plugin for PausedPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(InputManagerPlugin::<PauseMenuAction>::default())
+            .init_resource::<SelectedAudioBus>()
             .add_enter_system(PauseState::Paused, spawn_pause_menu)
             .add_exit_system(PauseState::Paused, despawn_entities_with::<PauseMenuItem>)
+            .add_exit_system(PauseState::Paused, save_settings)
             .add_startup_system(spawn_pause_menu_detector)
-            .add_system(change_pause_state.run_in_state(GameState::Game));
+            .add_system(change_pause_state.run_in_state(GameState::Game))
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Paused)
+                    .with_system(adjust_audio_settings)
+                    .with_system(update_audio_setting_text)
+                    .into(),
+            );
     }
 }
 
 #[derive(Default, Component)]
 struct PauseMenuItem;
 
-#[derive(Actionlike, Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum PauseMenuAction {
+#[derive(Actionlike, Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PauseMenuAction {
     Close,
     Open,
+    Up,
+    Down,
+    Left,
+    Right,
+    ToggleMute,
+    Controls,
+    ToggleCoop,
 }
 
+#[derive(Default)]
+struct SelectedAudioBus(usize);
+
+#[derive(Component)]
+struct AudioSettingText(AudioBus);
+
+const VOLUME_STEP: f32 = 0.1;
+
 fn spawn_pause_menu(mut commands: Commands, font_assets: Res<FontAssets>) {
+    let text_style = TextStyle {
+        font: font_assets.game.clone(),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+
     commands
         .spawn_bundle(NodeBundle {
             style: Style {
@@ -87,15 +120,51 @@ fn spawn_pause_menu(mut commands: Commands, font_assets: Res<FontAssets>) {
                 ..default()
             });
         })
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                style: Style {
+                    margin: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                text: Text::from_section(
+                    "Press Tab to change controls".to_string(),
+                    text_style.clone(),
+                ),
+                ..default()
+            });
+        })
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    style: Style {
+                        margin: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    text: Text::from_section(String::new(), text_style.clone()),
+                    ..default()
+                })
+                .insert(CoopText);
+        })
+        .with_children(|parent| {
+            for bus in AudioBus::ALL {
+                parent
+                    .spawn_bundle(TextBundle {
+                        style: Style {
+                            margin: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        text: Text::from_section(String::new(), text_style.clone()),
+                        ..default()
+                    })
+                    .insert(AudioSettingText(bus));
+            }
+        })
         .insert(PauseMenuItem);
 }
 
-fn spawn_pause_menu_detector(mut commands: Commands) {
+fn spawn_pause_menu_detector(mut commands: Commands, controls: Res<Controls>) {
     commands.spawn_bundle(InputManagerBundle {
-        input_map: InputMap::new([
-            (KeyCode::Escape, PauseMenuAction::Open),
-            (KeyCode::Return, PauseMenuAction::Close),
-        ]),
+        input_map: controls.menu_input_map(None),
         action_state: ActionState::default(),
     });
 }
@@ -106,12 +175,90 @@ fn change_pause_state(
     current_state: Res<CurrentState<PauseState>>,
 ) {
     for action in &action_query {
-        if action.pressed(PauseMenuAction::Close) && matches!(current_state.0, PauseState::Paused) {
+        // Open toggles so a single Start button both pauses and resumes.
+        if (action.just_pressed(PauseMenuAction::Close)
+            || action.just_pressed(PauseMenuAction::Open))
+            && matches!(current_state.0, PauseState::Paused)
+        {
             commands.insert_resource(NextState(PauseState::Unpaused));
-        } else if action.pressed(PauseMenuAction::Open)
+        } else if action.just_pressed(PauseMenuAction::Open)
             && matches!(current_state.0, PauseState::Unpaused)
         {
             commands.insert_resource(NextState(PauseState::Paused));
+        } else if action.just_pressed(PauseMenuAction::Controls)
+            && matches!(current_state.0, PauseState::Paused)
+        {
+            commands.insert_resource(NextState(PauseState::Controls));
         }
     }
 }
+
+fn adjust_audio_settings(
+    action_query: Query<&ActionState<PauseMenuAction>>,
+    mut selected: ResMut<SelectedAudioBus>,
+    mut save_data: ResMut<SaveData>,
+    ui_audio: Res<AudioChannel<UiChannel>>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for action in &action_query {
+        let count = AudioBus::ALL.len();
+
+        if action.just_pressed(PauseMenuAction::Up) {
+            selected.0 = (selected.0 + count - 1) % count;
+            ui_audio.play(audio_assets.ui_move.clone());
+        }
+        if action.just_pressed(PauseMenuAction::Down) {
+            selected.0 = (selected.0 + 1) % count;
+            ui_audio.play(audio_assets.ui_move.clone());
+        }
+
+        let bus = AudioBus::ALL[selected.0];
+        let step = if action.just_pressed(PauseMenuAction::Right) {
+            VOLUME_STEP
+        } else if action.just_pressed(PauseMenuAction::Left) {
+            -VOLUME_STEP
+        } else {
+            0.0
+        };
+
+        if step != 0.0 {
+            let setting = save_data.audio.bus_mut(bus);
+            // Rounded so repeated steps don't drift away from whole percentages.
+            setting.volume = ((setting.volume + step) * 10.0).round().clamp(0.0, 10.0) / 10.0;
+            ui_audio.play(audio_assets.ui_move.clone());
+        }
+        if action.just_pressed(PauseMenuAction::ToggleMute) {
+            let setting = save_data.audio.bus_mut(bus);
+            setting.muted = !setting.muted;
+            ui_audio.play(audio_assets.ui_select.clone());
+        }
+    }
+}
+
+fn update_audio_setting_text(
+    mut text_query: Query<(&mut Text, &AudioSettingText)>,
+    selected: Res<SelectedAudioBus>,
+    save_data: Res<SaveData>,
+) {
+    for (mut text, AudioSettingText(bus)) in &mut text_query {
+        let setting = save_data.audio.bus(*bus);
+        let is_selected = AudioBus::ALL[selected.0] == *bus;
+
+        let value = if setting.muted {
+            "Muted".to_string()
+        } else {
+            format!("{:.0}%", setting.volume * 100.0)
+        };
+
+        text.sections[0].value = format!("{}: {}", bus.label(), value);
+        text.sections[0].style.color = if is_selected {
+            Color::YELLOW
+        } else {
+            Color::WHITE
+        };
+    }
+}
+
+fn save_settings(save_data: Res<SaveData>) {
+    save_data.write();
+}
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use super::*;
 
 pub struct PlayerPlugin;
@@ -9,14 +11,16 @@ impl Plugin for PlayerPlugin {
             .add_system_set(
                 ConditionSet::new()
                     .run_in_state(GameState::Game)
-                    .run_not_in_state(PauseState::Paused)
-                    .with_system(player_movement)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(add_player_animation)
                     .with_system(player_animation)
                     .with_system(player_state_tracker)
                     .with_system(update_ability)
                     .with_system(spawn_past_player)
                     .with_system(update_past_player)
                     .with_system(remove_past_when_not_preforming)
+                    .with_system(spawn_second_player)
+                    .with_system(despawn_second_player)
                     .into(),
             );
     }
@@ -25,8 +29,16 @@ impl Plugin for PlayerPlugin {
 #[derive(Bundle, Default, LdtkEntity)]
 struct PlayerBundle {
     player: Player,
+    number: PlayerNumber,
     past_states: PlayerPastStates,
     ability_state: PlayerAbilityState,
+    timers: PlayerTimers,
+    facing: Facing,
+    inventory: Inventory,
+    surface: CurrentSurface,
+
+    #[worldly]
+    worldly: Worldly,
 
     #[bundle]
     collider: PlayerColliderBundle,
@@ -42,6 +54,33 @@ struct PlayerBundle {
 #[derive(Component, Default)]
 pub struct Player;
 
+pub const PLAYER_HALF_EXTENTS: Vec2 = Vec2::new(7.0, 9.0);
+
+/// Which player this is, the first is spawned by the level and the second joins in co-op.
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PlayerNumber(pub usize);
+
+impl PlayerNumber {
+    pub fn tint(&self) -> Color {
+        match self.0 {
+            0 => Color::WHITE,
+            _ => Color::rgb(1.0, 0.75, 0.5),
+        }
+    }
+
+    fn echo_tint(&self) -> Color {
+        match self.0 {
+            0 => Color::CYAN,
+            _ => Color::ORANGE,
+        }
+    }
+
+    /// Where the player stands relative to the others when they are all put in one place.
+    pub fn spawn_offset(&self) -> Vec3 {
+        Vec3::X * 16.0 * self.0 as f32
+    }
+}
+
 #[derive(Bundle)]
 struct PlayerColliderBundle {
     collider: Collider,
@@ -51,32 +90,36 @@ struct PlayerColliderBundle {
     locked_axis: LockedAxes,
     friction: Friction,
     dampening: Damping,
+    collision_groups: CollisionGroups,
 }
 
 impl Default for PlayerColliderBundle {
     fn default() -> Self {
         Self {
-            collider: Collider::cuboid(7.0, 9.0),
+            collider: Collider::cuboid(PLAYER_HALF_EXTENTS.x, PLAYER_HALF_EXTENTS.y),
             rigid_body: RigidBody::Dynamic,
             velocity: Velocity::zero(),
             gravity_scale: GravityScale(0.0),
             locked_axis: LockedAxes::ROTATION_LOCKED,
             friction: Friction::coefficient(0.0),
+            // Slowing down is handled by the movement controller.
             dampening: Damping {
-                linear_damping: 30.0,
+                linear_damping: 0.0,
                 angular_damping: 0.0,
             },
+            collision_groups: CollisionGroups::new(PLAYER_GROUP, ALL_GROUPS & !ECHO_GROUP),
         }
     }
 }
 
-#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug)]
-enum PlayerAction {
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Serialize, Deserialize)]
+pub enum PlayerAction {
     Up,
     Down,
     Left,
     Right,
     Ability,
+    Restart,
 }
 
 #[derive(Bundle)]
@@ -85,23 +128,12 @@ struct PlayerInput {
     input_manager: InputManagerBundle<PlayerAction>,
 }
 
+/// Bindings come from the `Controls` resource once the player is spawned.
 impl Default for PlayerInput {
     fn default() -> Self {
-        use PlayerAction::*;
-
         Self {
             input_manager: InputManagerBundle::<PlayerAction> {
-                input_map: InputMap::new([
-                    (KeyCode::W, Up),
-                    (KeyCode::S, Down),
-                    (KeyCode::A, Left),
-                    (KeyCode::D, Right),
-                    (KeyCode::Up, Up),
-                    (KeyCode::Down, Down),
-                    (KeyCode::Left, Left),
-                    (KeyCode::Right, Right),
-                    (KeyCode::Space, Ability),
-                ]),
+                input_map: Controls::default().player_input_map(PlayerNumber(0), false, None),
                 ..default()
             },
         }
@@ -109,7 +141,14 @@ impl Default for PlayerInput {
 }
 
 #[derive(Component, Default, Clone)]
-struct PlayerPastStates(Vec<PlayerPastState>);
+pub struct PlayerPastStates(Vec<PlayerPastState>);
+
+impl PlayerPastStates {
+    /// The recorded positions the echo replays next, oldest first.
+    pub fn upcoming_translations(&self) -> impl Iterator<Item = Vec3> + '_ {
+        self.0.iter().map(|past_state| past_state.translation)
+    }
+}
 
 #[derive(Clone)]
 struct PlayerPastState {
@@ -118,7 +157,7 @@ struct PlayerPastState {
 }
 
 #[derive(Component, Default, Debug)]
-enum PlayerAbilityState {
+pub enum PlayerAbilityState {
     Preforming,
     Cooldown,
 
@@ -126,154 +165,144 @@ enum PlayerAbilityState {
     Idle,
 }
 
-fn player_movement(
-    mut player_query: Query<
-        (&mut Velocity, &ActionState<PlayerAction>),
-        (With<Player>, Changed<ActionState<PlayerAction>>),
-    >,
-) {
-    for (mut vel, action_state) in &mut player_query {
-        if action_state.pressed(PlayerAction::Up) {
-            vel.linvel.y = 100.0;
-        }
-        if action_state.pressed(PlayerAction::Down) {
-            vel.linvel.y = -100.0;
-        }
-        if action_state.pressed(PlayerAction::Right) {
-            vel.linvel.x = 100.0;
-        }
-        if action_state.pressed(PlayerAction::Left) {
-            vel.linvel.x = -100.0;
+/// The way the player last walked, so they keep facing it when they stop.
+#[derive(Component, Default, Clone, Copy)]
+enum Facing {
+    #[default]
+    Down,
+    Up,
+    Left,
+    Right,
+}
+
+impl Facing {
+    fn name(&self) -> &'static str {
+        match self {
+            Facing::Down => "down",
+            Facing::Up => "up",
+            Facing::Left => "left",
+            Facing::Right => "right",
         }
     }
 }
 
-struct AnimationTimer(Timer);
-
-impl Default for AnimationTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(0.2, false))
+fn add_player_animation(
+    mut commands: Commands,
+    player_query: Query<Entity, Added<Player>>,
+    animation_assets: Res<AnimationAssets>,
+) {
+    for entity in &player_query {
+        commands.entity(entity).insert(SpriteAnimation::new(
+            animation_assets.player.clone(),
+            "idle_down",
+        ));
     }
 }
 
 fn player_animation(
     mut player_query: Query<
         (
+            &mut SpriteAnimation,
             &mut TextureAtlasSprite,
+            &mut Facing,
             &ActionState<PlayerAction>,
             &PlayerAbilityState,
+            &PlayerNumber,
         ),
         With<Player>,
     >,
-    mut timer: Local<AnimationTimer>,
-    time: Res<Time>,
 ) {
-    for (mut sprite, action_state, ability_state) in &mut player_query {
-        sprite.index = if action_state.pressed(PlayerAction::Left) {
-            timer.0.tick(time.delta());
-            8 + ((sprite.index
-                + if timer.0.finished() {
-                    timer.0.reset();
-                    1
-                } else {
-                    0
-                })
-                % 4)
+    for (mut animation, mut sprite, mut facing, action_state, ability_state, number) in
+        &mut player_query
+    {
+        let walking = if action_state.pressed(PlayerAction::Left) {
+            Some(Facing::Left)
         } else if action_state.pressed(PlayerAction::Right) {
-            timer.0.tick(time.delta());
-            12 + ((sprite.index
-                + if timer.0.finished() {
-                    timer.0.reset();
-                    1
-                } else {
-                    0
-                })
-                % 4)
+            Some(Facing::Right)
         } else if action_state.pressed(PlayerAction::Up) {
-            timer.0.tick(time.delta());
-            4 + ((sprite.index
-                + if timer.0.finished() {
-                    timer.0.reset();
-                    1
-                } else {
-                    0
-                })
-                % 4)
+            Some(Facing::Up)
         } else if action_state.pressed(PlayerAction::Down) {
-            timer.0.tick(time.delta());
-            0 + ((sprite.index
-                + if timer.0.finished() {
-                    timer.0.reset();
-                    1
-                } else {
-                    0
-                })
-                % 4)
-        } else if timer.0.finished() {
-            timer.0.reset();
-            sprite.index - sprite.index % 4
+            Some(Facing::Down)
         } else {
-            timer.0.tick(time.delta());
-            sprite.index
+            None
         };
 
+        match walking {
+            Some(direction) => {
+                *facing = direction;
+                animation.play(&format!("walk_{}", direction.name()));
+            }
+            None => animation.play(&format!("idle_{}", facing.name())),
+        }
+
         match ability_state {
             PlayerAbilityState::Cooldown => sprite.color = Color::GRAY,
-            _ => sprite.color = Color::WHITE,
+            _ => sprite.color = number.tint(),
         }
     }
 }
 
-struct StateTimer(Timer);
+/// Timers each player keeps for themselves, so several players never share one.
+#[derive(Component)]
+pub struct PlayerTimers {
+    /// How often the player's position is recorded for their echo.
+    record: Timer,
+    /// How long the ability can be used for in one go.
+    usage: Timer,
+    cooldown: Timer,
+    /// How far the player has walked since their last footstep sound.
+    pub stride: f32,
+}
 
-impl Default for StateTimer {
+impl Default for PlayerTimers {
     fn default() -> Self {
-        Self(Timer::from_seconds(0.05, false))
+        Self {
+            record: Timer::from_seconds(0.05, false),
+            usage: Timer::from_seconds(5.0, false),
+            cooldown: Timer::from_seconds(3.0, false),
+            stride: 0.0,
+        }
     }
 }
 
 fn player_state_tracker(
-    mut player_query: Query<(&mut PlayerPastStates, &TextureAtlasSprite, &Transform), With<Player>>,
-    mut timer: Local<StateTimer>,
+    mut player_query: Query<
+        (
+            &mut PlayerPastStates,
+            &mut PlayerTimers,
+            &TextureAtlasSprite,
+            &GlobalTransform,
+        ),
+        With<Player>,
+    >,
     time: Res<Time>,
 ) {
-    timer.0.tick(time.delta());
-    if timer.0.finished() {
-        for (mut past_states, sprite, transform) in player_query.iter_mut() {
+    for (mut past_states, mut timers, sprite, transform) in player_query.iter_mut() {
+        timers.record.tick(time.delta());
+        if timers.record.finished() {
             if past_states.0.len() > 100 {
                 past_states.0.remove(0);
             }
             past_states.0.push(PlayerPastState {
-                translation: transform.translation,
+                translation: transform.translation(),
                 index: sprite.index,
             });
         }
     }
 }
 
-struct CooldownTimer(Timer);
-
-impl Default for CooldownTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(3.0, false))
-    }
-}
-
-struct UsageTimer(Timer);
-
-impl Default for UsageTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(5.0, false))
-    }
-}
-
 fn update_ability(
-    mut player_query: Query<(&mut PlayerAbilityState, &ActionState<PlayerAction>), With<Player>>,
-    mut cooldown_timer: Local<CooldownTimer>,
-    mut usage_timer: Local<UsageTimer>,
+    mut player_query: Query<
+        (
+            &mut PlayerAbilityState,
+            &mut PlayerTimers,
+            &ActionState<PlayerAction>,
+        ),
+        With<Player>,
+    >,
     time: Res<Time>,
 ) {
-    for (mut ability_state, action_state) in &mut player_query {
+    for (mut ability_state, mut timers, action_state) in &mut player_query {
         match *ability_state {
             PlayerAbilityState::Idle => {
                 if action_state.pressed(PlayerAction::Ability)
@@ -288,22 +317,22 @@ fn update_ability(
                 }
             }
             PlayerAbilityState::Preforming => {
-                usage_timer.0.tick(time.delta());
+                timers.usage.tick(time.delta());
                 if !action_state.get_pressed().iter().any(|&x| {
                     x == PlayerAction::Up
                         || x == PlayerAction::Down
                         || x == PlayerAction::Left
                         || x == PlayerAction::Right
-                }) || usage_timer.0.finished()
+                }) || timers.usage.finished()
                 {
-                    usage_timer.0.reset();
+                    timers.usage.reset();
                     *ability_state = PlayerAbilityState::Cooldown;
                 }
             }
             PlayerAbilityState::Cooldown => {
-                cooldown_timer.0.tick(time.delta());
-                if cooldown_timer.0.finished() {
-                    cooldown_timer.0.reset();
+                timers.cooldown.tick(time.delta());
+                if timers.cooldown.finished() {
+                    timers.cooldown.reset();
                     *ability_state = PlayerAbilityState::Idle;
                 }
             }
@@ -315,22 +344,28 @@ fn spawn_past_player(
     mut commands: Commands,
     player_query: Query<
         (
+            Entity,
             &PlayerAbilityState,
             &PlayerPastStates,
+            &PlayerNumber,
             &Handle<TextureAtlas>,
         ),
         Changed<PlayerAbilityState>,
     >,
 ) {
-    for (ability_state, past_states, atlas) in &player_query {
+    for (entity, ability_state, past_states, number, atlas) in &player_query {
         if matches!(ability_state, PlayerAbilityState::Preforming) {
             commands
                 .spawn()
                 .insert(PastPlayer)
+                .insert(EchoOf(entity))
+                .insert(EchoOffset::default())
+                .insert(Inventory::default())
                 .insert_bundle(PlayerColliderBundle::default())
+                .insert(CollisionGroups::new(ECHO_GROUP, ALL_GROUPS & !PLAYER_GROUP))
                 .insert_bundle(SpriteSheetBundle {
                     sprite: TextureAtlasSprite {
-                        color: Color::CYAN,
+                        color: number.echo_tint(),
                         index: past_states.0.first().unwrap().index,
                         ..default()
                     },
@@ -348,29 +383,97 @@ fn spawn_past_player(
 
 fn update_past_player(
     player_query: Query<&PlayerPastStates, With<Player>>,
-    mut past_player_query: Query<(&mut Transform, &mut TextureAtlasSprite), With<PastPlayer>>,
+    mut past_player_query: Query<
+        (
+            &mut Transform,
+            &mut TextureAtlasSprite,
+            &EchoOf,
+            &EchoOffset,
+        ),
+        With<PastPlayer>,
+    >,
 ) {
-    for past_states in &player_query {
-        for (mut transform, mut sprite) in &mut past_player_query {
-            transform.translation = past_states.0.first().unwrap().translation;
+    for (mut transform, mut sprite, EchoOf(owner), offset) in &mut past_player_query {
+        if let Ok(past_states) = player_query.get(*owner) {
+            transform.translation = past_states.0.first().unwrap().translation + offset.0;
             sprite.index = past_states.0.first().unwrap().index;
         }
     }
 }
 
+/// Ends the echo once its player stops using the ability, handing any keys it picked up to
+/// the player so they aren't lost with it.
 fn remove_past_when_not_preforming(
     mut commands: Commands,
-    player_query: Query<&PlayerAbilityState, Changed<PlayerAbilityState>>,
-    past_player_query: Query<Entity, (With<PastPlayer>, Without<Player>)>,
+    mut player_query: Query<(&PlayerAbilityState, &mut Inventory), With<Player>>,
+    past_player_query: Query<(Entity, &EchoOf, &Inventory), (With<PastPlayer>, Without<Player>)>,
 ) {
-    for ability_state in &player_query {
-        if !matches!(ability_state, PlayerAbilityState::Preforming) {
-            for past_player_entity in &past_player_query {
-                commands.entity(past_player_entity).despawn_recursive();
+    for (past_player_entity, EchoOf(owner), echo_inventory) in &past_player_query {
+        let owner = player_query.get_mut(*owner).ok();
+        let preforming = owner.as_ref().map_or(false, |(ability_state, _)| {
+            matches!(ability_state, PlayerAbilityState::Preforming)
+        });
+
+        if !preforming {
+            if let Some((_, mut inventory)) = owner {
+                inventory.keys.extend(&echo_inventory.keys);
             }
+            commands.entity(past_player_entity).despawn_recursive();
+        }
+    }
+}
+
+/// The player an echo is replaying.
+#[derive(Component)]
+pub struct EchoOf(pub Entity);
+
+/// How far teleporters have moved an echo away from the path it replays.
+#[derive(Component, Default)]
+pub struct EchoOffset(pub Vec3);
+
+fn spawn_second_player(
+    mut commands: Commands,
+    player_query: Query<(&GlobalTransform, &Handle<TextureAtlas>), With<Player>>,
+    coop: Res<CoopMode>,
+    rapier_context: Res<RapierContext>,
+) {
+    if !coop.0 || player_query.iter().count() != 1 {
+        return;
+    }
+
+    let (transform, atlas) = player_query.single();
+    let number = PlayerNumber(1);
+
+    commands.spawn_bundle(PlayerBundle {
+        number,
+        sprite_bundle: SpriteSheetBundle {
+            texture_atlas: atlas.clone(),
+            transform: Transform::from_translation(partner_position(
+                &rapier_context,
+                transform.translation(),
+                number,
+            )),
+            ..default()
+        },
+        ..default()
+    });
+}
+
+fn despawn_second_player(
+    mut commands: Commands,
+    player_query: Query<(Entity, &PlayerNumber), With<Player>>,
+    coop: Res<CoopMode>,
+) {
+    if coop.0 {
+        return;
+    }
+
+    for (entity, number) in &player_query {
+        if number.0 > 0 {
+            commands.entity(entity).despawn_recursive();
         }
     }
 }
 
 #[derive(Component, Default)]
-struct PastPlayer;
+pub struct PastPlayer;
@@ -0,0 +1,315 @@
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+
+/// An inclusive rectangle of grid cells.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct GridRect {
+    pub left: i32,
+    pub right: i32,
+    pub top: i32,
+    pub bottom: i32,
+}
+
+impl GridRect {
+    pub fn contains(&self, coords: GridCoords) -> bool {
+        (self.left..=self.right).contains(&coords.x) && (self.bottom..=self.top).contains(&coords.y)
+    }
+
+    pub fn area(&self) -> i32 {
+        (self.right - self.left + 1) * (self.top - self.bottom + 1)
+    }
+
+    pub fn centre(&self, grid_size: f32) -> Vec2 {
+        Vec2::new(
+            (self.left + self.right + 1) as f32 * grid_size / 2.,
+            (self.bottom + self.top + 1) as f32 * grid_size / 2.,
+        )
+    }
+
+    pub fn half_extents(&self, grid_size: f32) -> Vec2 {
+        Vec2::new(
+            (self.right - self.left + 1) as f32 * grid_size / 2.,
+            (self.top - self.bottom + 1) as f32 * grid_size / 2.,
+        )
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
+struct Plate {
+    left: i32,
+    right: i32,
+}
+
+/// Covers `cells` with non-overlapping rectangles.
+///
+/// Each row is first split into horizontal plates of consecutive cells, then plates with the
+/// same extent in consecutive rows are merged upwards. Cells outside `width` by `height` are
+/// ignored.
+pub fn merge_grid_rects(cells: &HashSet<GridCoords>, width: i32, height: i32) -> Vec<GridRect> {
+    let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
+
+    for y in 0..height {
+        let mut row_plates: Vec<Plate> = Vec::new();
+        let mut plate_start = None;
+
+        for x in 0..width + 1 {
+            match (plate_start, cells.contains(&GridCoords { x, y })) {
+                (Some(s), false) => {
+                    row_plates.push(Plate {
+                        left: s,
+                        right: x - 1,
+                    });
+                    plate_start = None;
+                }
+                (None, true) if x < width => plate_start = Some(x),
+                _ => (),
+            }
+        }
+
+        plate_stack.push(row_plates);
+    }
+
+    let mut rects: Vec<GridRect> = Vec::new();
+    let mut previous_rects: HashMap<Plate, GridRect> = HashMap::new();
+
+    plate_stack.push(Vec::new());
+
+    for (y, row) in plate_stack.iter().enumerate() {
+        let mut current_rects: HashMap<Plate, GridRect> = HashMap::new();
+        for plate in row {
+            if let Some(previous_rect) = previous_rects.remove(plate) {
+                current_rects.insert(
+                    *plate,
+                    GridRect {
+                        top: previous_rect.top + 1,
+                        ..previous_rect
+                    },
+                );
+            } else {
+                current_rects.insert(
+                    *plate,
+                    GridRect {
+                        bottom: y as i32,
+                        top: y as i32,
+                        left: plate.left,
+                        right: plate.right,
+                    },
+                );
+            }
+        }
+
+        rects.extend(previous_rects.values().copied());
+        previous_rects = current_rects;
+    }
+
+    rects
+}
+
+/// Covers `cells` with non-overlapping rectangles by repeatedly taking the largest rectangle
+/// left in the grid.
+///
+/// This is slower than [`merge_grid_rects`] but usually produces noticeably fewer rectangles in
+/// large rooms with irregular walls.
+pub fn cover_grid_rects(cells: &HashSet<GridCoords>, width: i32, height: i32) -> Vec<GridRect> {
+    let mut remaining: HashSet<GridCoords> = cells
+        .iter()
+        .copied()
+        .filter(|coords| (0..width).contains(&coords.x) && (0..height).contains(&coords.y))
+        .collect();
+    let mut rects = Vec::new();
+
+    while let Some(rect) = largest_rect(&remaining, width, height) {
+        for x in rect.left..=rect.right {
+            for y in rect.bottom..=rect.top {
+                remaining.remove(&GridCoords { x, y });
+            }
+        }
+        rects.push(rect);
+    }
+
+    rects
+}
+
+/// Finds the largest rectangle of `cells`, scanning each row as a histogram of column heights.
+fn largest_rect(cells: &HashSet<GridCoords>, width: i32, height: i32) -> Option<GridRect> {
+    let mut heights = vec![0; width as usize];
+    let mut best: Option<GridRect> = None;
+
+    for y in 0..height {
+        for x in 0..width {
+            heights[x as usize] = if cells.contains(&GridCoords { x, y }) {
+                heights[x as usize] + 1
+            } else {
+                0
+            };
+        }
+
+        let mut stack: Vec<usize> = Vec::new();
+        for x in 0..=width as usize {
+            let current = if x < width as usize { heights[x] } else { 0 };
+
+            while let Some(&top) = stack.last() {
+                if heights[top] <= current {
+                    break;
+                }
+                stack.pop();
+
+                let left = stack.last().map_or(0, |&v| v + 1);
+                let rect = GridRect {
+                    left: left as i32,
+                    right: x as i32 - 1,
+                    top: y,
+                    bottom: y - heights[top] + 1,
+                };
+
+                if best.map_or(true, |best| rect.area() > best.area()) {
+                    best = Some(rect);
+                }
+            }
+
+            stack.push(x);
+        }
+    }
+
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: i32 = 12;
+    const HEIGHT: i32 = 10;
+
+    /// Xorshift, so the random grids are the same on every run without another dependency.
+    struct Rng(u64);
+
+    impl Rng {
+        fn new(seed: u64) -> Self {
+            Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+        }
+
+        fn below(&mut self, bound: u32) -> u32 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as u32
+        }
+    }
+
+    fn random_grid(rng: &mut Rng, fill: u32) -> HashSet<GridCoords> {
+        let mut cells = HashSet::new();
+        for x in 0..WIDTH {
+            for y in 0..HEIGHT {
+                if rng.below(100) < fill {
+                    cells.insert(GridCoords { x, y });
+                }
+            }
+        }
+        cells
+    }
+
+    fn rect_cells(rect: &GridRect) -> impl Iterator<Item = GridCoords> + '_ {
+        (rect.left..=rect.right)
+            .flat_map(|x| (rect.bottom..=rect.top).map(move |y| GridCoords { x, y }))
+    }
+
+    /// The rects cover exactly `cells`, each cell once.
+    fn assert_exact_cover(cells: &HashSet<GridCoords>, rects: &[GridRect]) {
+        let mut covered = HashSet::new();
+        for rect in rects {
+            assert!(
+                rect.left <= rect.right && rect.bottom <= rect.top,
+                "{rect:?} is empty"
+            );
+            for coords in rect_cells(rect) {
+                assert!(
+                    cells.contains(&coords),
+                    "{rect:?} covers empty cell {coords:?}"
+                );
+                assert!(covered.insert(coords), "{coords:?} is covered twice");
+            }
+        }
+        assert_eq!(&covered, cells);
+    }
+
+    fn check_decomposition(decompose: fn(&HashSet<GridCoords>, i32, i32) -> Vec<GridRect>) {
+        for seed in 0..200 {
+            let mut rng = Rng::new(seed);
+            let fill = rng.below(101);
+            let cells = random_grid(&mut rng, fill);
+            assert_exact_cover(&cells, &decompose(&cells, WIDTH, HEIGHT));
+        }
+    }
+
+    #[test]
+    fn merge_grid_rects_covers_random_grids() {
+        check_decomposition(merge_grid_rects);
+    }
+
+    #[test]
+    fn cover_grid_rects_covers_random_grids() {
+        check_decomposition(cover_grid_rects);
+    }
+
+    #[test]
+    fn decompositions_ignore_cells_outside_the_grid() {
+        let inside = GridCoords { x: 0, y: 0 };
+        let cells = HashSet::from([
+            inside,
+            GridCoords { x: WIDTH, y: 0 },
+            GridCoords { x: 0, y: HEIGHT },
+            GridCoords { x: -1, y: 0 },
+        ]);
+        let expected = HashSet::from([inside]);
+
+        assert_exact_cover(&expected, &merge_grid_rects(&cells, WIDTH, HEIGHT));
+        assert_exact_cover(&expected, &cover_grid_rects(&cells, WIDTH, HEIGHT));
+    }
+
+    #[test]
+    fn full_grid_is_one_rect() {
+        let cells = random_grid(&mut Rng::new(0), 100);
+        let full = GridRect {
+            left: 0,
+            right: WIDTH - 1,
+            top: HEIGHT - 1,
+            bottom: 0,
+        };
+
+        assert_eq!(merge_grid_rects(&cells, WIDTH, HEIGHT), vec![full]);
+        assert_eq!(cover_grid_rects(&cells, WIDTH, HEIGHT), vec![full]);
+    }
+
+    #[test]
+    fn largest_rect_is_filled_and_maximal() {
+        for seed in 0..200 {
+            let mut rng = Rng::new(seed);
+            let fill = rng.below(101);
+            let cells = random_grid(&mut rng, fill);
+
+            let best_area = (0..WIDTH)
+                .flat_map(|left| (left..WIDTH).map(move |right| (left, right)))
+                .flat_map(|(left, right)| {
+                    (0..HEIGHT).flat_map(move |bottom| {
+                        (bottom..HEIGHT).map(move |top| GridRect {
+                            left,
+                            right,
+                            top,
+                            bottom,
+                        })
+                    })
+                })
+                .filter(|rect| rect_cells(rect).all(|coords| cells.contains(&coords)))
+                .map(|rect| rect.area())
+                .max();
+
+            let found = largest_rect(&cells, WIDTH, HEIGHT);
+            assert_eq!(found.map(|rect| rect.area()), best_area);
+            if let Some(rect) = found {
+                assert!(rect_cells(&rect).all(|coords| cells.contains(&coords)));
+            }
+        }
+    }
+}
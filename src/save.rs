@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+const SAVE_PATH: &str = "save.ron";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SaveData::load());
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    pub inventory: Vec<KeyColour>,
+    /// Keys carried by the second player in co-op.
+    pub player_two_inventory: Vec<KeyColour>,
+    pub display: DisplaySettings,
+    pub audio: AudioSettings,
+    /// Iids of levels the player has entered.
+    pub visited_levels: Vec<String>,
+    /// Iids of doors the player has opened at least once.
+    pub opened_doors: Vec<String>,
+    /// Iids of keys picked up before entering the current room.
+    pub collected_keys: Vec<String>,
+    /// Iids of locked doors a key was spent on before entering the current room.
+    pub unlocked_doors: Vec<String>,
+}
+
+impl SaveData {
+    /// The saved keys of one player.
+    pub fn inventory_mut(&mut self, player: PlayerNumber) -> &mut Vec<KeyColour> {
+        match player.0 {
+            0 => &mut self.inventory,
+            _ => &mut self.player_two_inventory,
+        }
+    }
+
+    pub fn load() -> Self {
+        std::fs::read_to_string(SAVE_PATH)
+            .ok()
+            .and_then(|contents| ron::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn write(&self) {
+        match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => {
+                if let Err(error) = std::fs::write(SAVE_PATH, contents) {
+                    warn!("Could not write save file: {error}");
+                }
+            }
+            Err(error) => warn!("Could not serialize save data: {error}"),
+        }
+    }
+}
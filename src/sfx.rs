@@ -0,0 +1,454 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy_kira_audio::InstanceHandle;
+
+use super::*;
+
+pub struct SfxPlugin;
+
+impl Plugin for SfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<AmbientEmitterBundle>("AmbientEmitter")
+            .add_channel_pool::<SfxVoice>(VOICE_COUNT)
+            .add_event::<SfxEvent>()
+            .init_resource::<SfxVoicePool>()
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(switch_sounds)
+                    .with_system(ability_sounds)
+                    .with_system(echo_sounds)
+                    .with_system(box_push_sounds)
+                    .with_system(footstep_sounds)
+                    .with_system(ambient_emitter_sounds)
+                    .into(),
+            )
+            .add_system(play_sound_effects.run_in_state(GameState::Game));
+    }
+}
+
+const VOICE_COUNT: usize = 6;
+/// Distance the player walks between footsteps.
+const STRIDE: f32 = 14.0;
+const BOX_PUSH_SPEED: f32 = 15.0;
+/// How far the panning moves either side of the centre for sounds at the edge of the view.
+const PAN_WIDTH: f32 = 0.4;
+/// Distance past the edge of the view over which sounds fade out.
+const FALLOFF_DISTANCE: f32 = 160.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SoundEffect {
+    SwitchPress,
+    SwitchRelease,
+    DoorOpen,
+    DoorClose,
+    AbilityStart,
+    AbilityEnd,
+    EchoSpawn,
+    EchoDespawn,
+    BoxPush,
+    Footstep,
+    FootstepIce,
+    FootstepMud,
+    AmbientDrip,
+    AmbientMachine,
+}
+
+struct SfxSettings {
+    bus: AudioBus,
+    volume: f32,
+    /// How far either side of normal speed the pitch is randomly shifted.
+    pitch_variation: f32,
+    max_concurrent: usize,
+    min_interval: f64,
+}
+
+impl SoundEffect {
+    fn clip(&self, audio_assets: &AudioAssets) -> Handle<AudioSource> {
+        match self {
+            SoundEffect::SwitchPress => audio_assets.switch_press.clone(),
+            SoundEffect::SwitchRelease => audio_assets.switch_release.clone(),
+            SoundEffect::DoorOpen => audio_assets.door_open.clone(),
+            SoundEffect::DoorClose => audio_assets.door_close.clone(),
+            SoundEffect::AbilityStart => audio_assets.ability_start.clone(),
+            SoundEffect::AbilityEnd => audio_assets.ability_end.clone(),
+            SoundEffect::EchoSpawn => audio_assets.echo_spawn.clone(),
+            SoundEffect::EchoDespawn => audio_assets.echo_despawn.clone(),
+            SoundEffect::BoxPush => audio_assets.box_push.clone(),
+            SoundEffect::Footstep => audio_assets.footstep.clone(),
+            SoundEffect::FootstepIce => audio_assets.footstep_ice.clone(),
+            SoundEffect::FootstepMud => audio_assets.footstep_mud.clone(),
+            SoundEffect::AmbientDrip => audio_assets.drip.clone(),
+            SoundEffect::AmbientMachine => audio_assets.machine.clone(),
+        }
+    }
+
+    fn settings(&self) -> SfxSettings {
+        match self {
+            SoundEffect::SwitchPress | SoundEffect::SwitchRelease => SfxSettings {
+                bus: AudioBus::Sfx,
+                volume: 0.6,
+                pitch_variation: 0.05,
+                max_concurrent: 2,
+                min_interval: 0.05,
+            },
+            SoundEffect::DoorOpen | SoundEffect::DoorClose => SfxSettings {
+                bus: AudioBus::Sfx,
+                volume: 0.7,
+                pitch_variation: 0.1,
+                max_concurrent: 2,
+                min_interval: 0.1,
+            },
+            SoundEffect::AbilityStart | SoundEffect::AbilityEnd => SfxSettings {
+                bus: AudioBus::Sfx,
+                volume: 0.8,
+                pitch_variation: 0.0,
+                max_concurrent: 1,
+                min_interval: 0.0,
+            },
+            SoundEffect::EchoSpawn | SoundEffect::EchoDespawn => SfxSettings {
+                bus: AudioBus::Sfx,
+                volume: 0.5,
+                pitch_variation: 0.1,
+                max_concurrent: 1,
+                min_interval: 0.1,
+            },
+            SoundEffect::BoxPush => SfxSettings {
+                bus: AudioBus::Sfx,
+                volume: 0.5,
+                pitch_variation: 0.15,
+                max_concurrent: 2,
+                min_interval: 0.2,
+            },
+            SoundEffect::Footstep | SoundEffect::FootstepIce | SoundEffect::FootstepMud => {
+                SfxSettings {
+                    bus: AudioBus::Sfx,
+                    volume: 0.4,
+                    pitch_variation: 0.2,
+                    max_concurrent: 2,
+                    min_interval: 0.08,
+                }
+            }
+            SoundEffect::AmbientDrip | SoundEffect::AmbientMachine => SfxSettings {
+                bus: AudioBus::Ambience,
+                volume: 0.6,
+                pitch_variation: 0.1,
+                max_concurrent: 2,
+                min_interval: 0.0,
+            },
+        }
+    }
+}
+
+/// A sound effect and where in the world it comes from, if anywhere.
+pub struct SfxEvent(pub SoundEffect, pub Option<Vec2>);
+
+/// Marks the channel pool sound effects play on. One sound at a time is played on each voice so
+/// it can have its own volume and pitch.
+pub struct SfxVoice;
+
+type SfxVoices<'w, 's> = ChannelPool<'w, 's, SfxVoice>;
+
+fn play_effect(
+    voice: &dyn PooledChannel,
+    clip: Handle<AudioSource>,
+    volume: f32,
+    playback_rate: f32,
+    panning: f32,
+) -> InstanceHandle {
+    voice.stop();
+    voice.set_volume(volume);
+    voice.set_playback_rate(playback_rate);
+    voice.set_panning(panning);
+    voice.play(clip)
+}
+
+struct PlayingSfx {
+    effect: SoundEffect,
+    instance: InstanceHandle,
+    started: f64,
+}
+
+#[derive(Default)]
+struct SfxVoicePool {
+    voices: [Option<PlayingSfx>; VOICE_COUNT],
+    last_played: HashMap<SoundEffect, f64>,
+}
+
+/// Panning and volume for a sound heard from the middle of the camera view.
+fn spatialise(position: Option<Vec2>, listener: Option<(Vec2, Vec2)>) -> (f32, f32) {
+    match (position, listener) {
+        (Some(position), Some((centre, half_view))) => {
+            let offset = position - centre;
+            let panning = 0.5 + PAN_WIDTH * (offset.x / half_view.x.max(1.0)).clamp(-1.0, 1.0);
+
+            // Anything on screen plays at full volume.
+            let outside = (offset.abs() - half_view).max(Vec2::ZERO).length();
+            let gain = (1.0 - outside / FALLOFF_DISTANCE).max(0.0);
+
+            (panning, gain)
+        }
+        _ => (0.5, 1.0),
+    }
+}
+
+fn play_sound_effects(
+    mut sfx_events: EventReader<SfxEvent>,
+    mut pool: ResMut<SfxVoicePool>,
+    voices: SfxVoices,
+    camera_query: Query<(&GlobalTransform, &OrthographicProjection), With<Camera2d>>,
+    audio_assets: Res<AudioAssets>,
+    save_data: Res<SaveData>,
+    time: Res<Time>,
+) {
+    let now = time.seconds_since_startup();
+
+    let listener =
+        camera_query
+            .get_single()
+            .ok()
+            .map(|(camera_transform, orthographic_projection)| {
+                let half_view =
+                    Vec2::new(orthographic_projection.right, orthographic_projection.top) / 2.0;
+                (
+                    camera_transform.translation().truncate() + half_view,
+                    half_view,
+                )
+            });
+
+    for (index, slot) in pool.voices.iter_mut().enumerate() {
+        // Sounds that have only just been queued don't report as playing yet.
+        let finished = slot.as_ref().map_or(false, |playing| {
+            now - playing.started > 0.1 && !voices.channel(index).is_playing(&playing.instance)
+        });
+
+        if finished {
+            *slot = None;
+        }
+    }
+
+    for SfxEvent(effect, position) in sfx_events.iter() {
+        let settings = effect.settings();
+
+        let (panning, gain) = spatialise(*position, listener);
+        if gain == 0.0 {
+            continue;
+        }
+
+        let playing = pool
+            .voices
+            .iter()
+            .flatten()
+            .filter(|playing| playing.effect == *effect)
+            .count();
+        let too_soon = pool
+            .last_played
+            .get(effect)
+            .map_or(false, |last| now - last < settings.min_interval);
+
+        if playing >= settings.max_concurrent || too_soon {
+            continue;
+        }
+
+        // Use a free voice, or cut off whichever sound has been playing longest.
+        let index = pool
+            .voices
+            .iter()
+            .position(Option::is_none)
+            .or_else(|| {
+                (0..VOICE_COUNT).min_by(|&a, &b| {
+                    let started =
+                        |index: usize| pool.voices[index].as_ref().map_or(0.0, |v| v.started);
+                    started(a).total_cmp(&started(b))
+                })
+            })
+            .unwrap_or_default();
+
+        let playback_rate = 1.0 + (fastrand::f32() * 2.0 - 1.0) * settings.pitch_variation;
+        let instance = play_effect(
+            voices.channel(index),
+            effect.clip(&audio_assets),
+            settings.volume * gain * save_data.audio.volume(settings.bus),
+            playback_rate,
+            panning,
+        );
+
+        pool.voices[index] = Some(PlayingSfx {
+            effect: *effect,
+            instance,
+            started: now,
+        });
+        pool.last_played.insert(*effect, now);
+    }
+}
+
+fn switch_sounds(
+    mut pressed_events: EventReader<SwitchPressedEvent>,
+    mut sfx_event: EventWriter<SfxEvent>,
+    switch_query: Query<&GlobalTransform>,
+) {
+    for SwitchPressedEvent(entity, state) in pressed_events.iter() {
+        let effect = match state {
+            SwitchState::Pressed => SoundEffect::SwitchPress,
+            SwitchState::Released => SoundEffect::SwitchRelease,
+        };
+        let position = switch_query
+            .get(*entity)
+            .ok()
+            .map(|switch_transform| switch_transform.translation().truncate());
+
+        sfx_event.send(SfxEvent(effect, position));
+    }
+}
+
+fn ability_sounds(
+    player_query: Query<&PlayerAbilityState, (With<Player>, Changed<PlayerAbilityState>)>,
+    mut sfx_event: EventWriter<SfxEvent>,
+) {
+    for ability_state in &player_query {
+        match ability_state {
+            PlayerAbilityState::Preforming => {
+                sfx_event.send(SfxEvent(SoundEffect::AbilityStart, None))
+            }
+            PlayerAbilityState::Cooldown => sfx_event.send(SfxEvent(SoundEffect::AbilityEnd, None)),
+            PlayerAbilityState::Idle => {}
+        }
+    }
+}
+
+fn echo_sounds(
+    added_query: Query<(), Added<PastPlayer>>,
+    removed: RemovedComponents<PastPlayer>,
+    mut sfx_event: EventWriter<SfxEvent>,
+) {
+    if !added_query.is_empty() {
+        sfx_event.send(SfxEvent(SoundEffect::EchoSpawn, None));
+    }
+
+    if removed.iter().next().is_some() {
+        sfx_event.send(SfxEvent(SoundEffect::EchoDespawn, None));
+    }
+}
+
+fn box_push_sounds(
+    box_query: Query<(Entity, &Velocity, &GlobalTransform), With<Box>>,
+    mut sfx_event: EventWriter<SfxEvent>,
+    mut moving: Local<HashSet<Entity>>,
+) {
+    for (entity, velocity, box_transform) in &box_query {
+        if velocity.linvel.length() > BOX_PUSH_SPEED {
+            if moving.insert(entity) {
+                sfx_event.send(SfxEvent(
+                    SoundEffect::BoxPush,
+                    Some(box_transform.translation().truncate()),
+                ));
+            }
+        } else {
+            moving.remove(&entity);
+        }
+    }
+}
+
+fn footstep_sounds(
+    mut player_query: Query<
+        (
+            &Velocity,
+            &CurrentSurface,
+            &GlobalTransform,
+            &mut PlayerTimers,
+        ),
+        With<Player>,
+    >,
+    mut sfx_event: EventWriter<SfxEvent>,
+    time: Res<Time>,
+) {
+    for (velocity, surface, player_transform, mut timers) in &mut player_query {
+        timers.stride += velocity.linvel.length() * time.delta_seconds();
+
+        if timers.stride >= STRIDE {
+            timers.stride = 0.0;
+            let effect = match surface.0 {
+                Surface::Ice => SoundEffect::FootstepIce,
+                Surface::Mud => SoundEffect::FootstepMud,
+                Surface::Normal | Surface::Conveyor(_) => SoundEffect::Footstep,
+            };
+            sfx_event.send(SfxEvent(
+                effect,
+                Some(player_transform.translation().truncate()),
+            ));
+        }
+    }
+}
+
+/// Plays a sound from its position in the level every so often.
+#[derive(Component)]
+pub struct AmbientEmitter {
+    sound: SoundEffect,
+    timer: Timer,
+}
+
+impl Default for AmbientEmitter {
+    fn default() -> Self {
+        Self {
+            sound: SoundEffect::AmbientDrip,
+            timer: Timer::from_seconds(2.0, true),
+        }
+    }
+}
+
+impl From<EntityInstance> for AmbientEmitter {
+    fn from(entity_instance: EntityInstance) -> Self {
+        let sound = match &entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "Sound")
+            .expect("Ambient emitter entity must have a sound field")
+            .value
+        {
+            FieldValue::Enum(Some(v)) if v == "Drip" => SoundEffect::AmbientDrip,
+            FieldValue::Enum(Some(v)) if v == "Machine" => SoundEffect::AmbientMachine,
+            _ => panic!("Ambient emitter sound field must be an enum"),
+        };
+
+        let interval = match &entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "Interval")
+            .expect("Ambient emitter entity must have an interval field")
+            .value
+        {
+            FieldValue::Float(Some(interval)) => *interval,
+            _ => panic!("Ambient emitter interval field must be a float"),
+        };
+
+        Self {
+            sound,
+            timer: Timer::from_seconds(interval, true),
+        }
+    }
+}
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct AmbientEmitterBundle {
+    #[from_entity_instance]
+    emitter: AmbientEmitter,
+
+    #[bundle]
+    spatial: SpatialBundle,
+}
+
+fn ambient_emitter_sounds(
+    mut emitter_query: Query<(&mut AmbientEmitter, &GlobalTransform)>,
+    mut sfx_event: EventWriter<SfxEvent>,
+    time: Res<Time>,
+) {
+    for (mut emitter, emitter_transform) in &mut emitter_query {
+        if emitter.timer.tick(time.delta()).just_finished() {
+            sfx_event.send(SfxEvent(
+                emitter.sound,
+                Some(emitter_transform.translation().truncate()),
+            ));
+        }
+    }
+}
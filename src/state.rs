@@ -9,4 +9,8 @@ pub enum GameState {
 pub enum PauseState {
     Paused,
     Unpaused,
+    /// Gameplay is frozen while the camera pans between rooms.
+    RoomTransition,
+    Map,
+    Controls,
 }
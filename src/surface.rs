@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use super::*;
+
+pub struct SurfacePlugin;
+
+impl Plugin for SurfacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FloorSurfaces>()
+            .add_enter_system(GameState::Game, load_floor_surfaces)
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(update_current_surface)
+                    .with_system(apply_surface_damping)
+                    .with_system(apply_conveyors)
+                    .into(),
+            );
+    }
+}
+
+const CONVEYOR_SPEED: f32 = 40.0;
+
+/// What a body is standing on, read from the `PlacmentGrid` IntGrid layer under it.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Surface {
+    #[default]
+    Normal,
+    Ice,
+    Mud,
+    Conveyor(Vec2),
+}
+
+impl Surface {
+    fn from_identifier(identifier: &str) -> Option<Self> {
+        match identifier {
+            "Ice" => Some(Surface::Ice),
+            "Mud" => Some(Surface::Mud),
+            "ConveyorUp" => Some(Surface::Conveyor(Vec2::Y)),
+            "ConveyorDown" => Some(Surface::Conveyor(Vec2::NEG_Y)),
+            "ConveyorLeft" => Some(Surface::Conveyor(Vec2::NEG_X)),
+            "ConveyorRight" => Some(Surface::Conveyor(Vec2::X)),
+            _ => None,
+        }
+    }
+
+    fn damping_factor(&self) -> f32 {
+        match self {
+            Surface::Ice => 0.05,
+            Surface::Mud => 2.0,
+            Surface::Normal | Surface::Conveyor(_) => 1.0,
+        }
+    }
+
+    /// How fast the player can walk on this surface compared to normal floor.
+    pub fn speed_factor(&self) -> f32 {
+        match self {
+            Surface::Mud => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    /// How quickly the player can speed up or slow down here, slippery floors give less grip.
+    pub fn traction(&self) -> f32 {
+        self.damping_factor().min(1.0)
+    }
+
+    /// The velocity the surface carries bodies along at.
+    pub fn drift(&self) -> Vec2 {
+        match self {
+            Surface::Conveyor(direction) => *direction * CONVEYOR_SPEED,
+            _ => Vec2::ZERO,
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct CurrentSurface(pub Surface);
+
+/// Maps values of the `PlacmentGrid` IntGrid layer to surfaces.
+#[derive(Default)]
+pub struct FloorSurfaces(HashMap<i32, Surface>);
+
+fn load_floor_surfaces(
+    mut surfaces: ResMut<FloorSurfaces>,
+    levels: Res<LevelsAsset>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    let layer = ldtk_assets
+        .get(&levels.map)
+        .expect("Levels should be loaded by this point")
+        .project
+        .defs
+        .layers
+        .iter()
+        .find(|v| v.identifier == "PlacmentGrid")
+        .expect("Project should have a PlacmentGrid layer");
+
+    surfaces.0 = layer
+        .int_grid_values
+        .iter()
+        .filter_map(|value| {
+            let surface = Surface::from_identifier(value.identifier.as_deref()?)?;
+            Some((value.value, surface))
+        })
+        .collect();
+}
+
+/// The value of the IntGrid layer cell under `translation`, if the level has that layer.
+fn cell_value(
+    ldtk_level: &LdtkLevel,
+    layer_identifier: &str,
+    level_translation: Vec2,
+    translation: Vec2,
+) -> Option<i32> {
+    let layer = ldtk_level
+        .level
+        .layer_instances
+        .as_ref()?
+        .iter()
+        .find(|v| v.identifier == layer_identifier)?;
+
+    let cell = ((translation - level_translation) / layer.grid_size as f32)
+        .floor()
+        .as_ivec2();
+
+    if !(0..layer.c_wid).contains(&cell.x) || !(0..layer.c_hei).contains(&cell.y) {
+        return None;
+    }
+
+    // LDtk stores rows from the top, grid coords count them from the bottom.
+    let index = (layer.c_hei - 1 - cell.y) * layer.c_wid + cell.x;
+    layer.int_grid_csv.get(index as usize).copied()
+}
+
+fn update_current_surface(
+    mut body_query: Query<(&GlobalTransform, &mut CurrentSurface)>,
+    level_query: Query<(&GlobalTransform, &Handle<LdtkLevel>)>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    surfaces: Res<FloorSurfaces>,
+    behaviours: Res<CollisionBehaviours>,
+) {
+    for (body_transform, mut current_surface) in &mut body_query {
+        let body_translation = body_transform.translation().truncate();
+        let mut surface = Surface::Normal;
+
+        for (level_transform, level_handle) in &level_query {
+            if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
+                let level_translation = level_transform.translation().truncate();
+
+                if let Some(&found) = cell_value(
+                    ldtk_level,
+                    "PlacmentGrid",
+                    level_translation,
+                    body_translation,
+                )
+                .and_then(|value| surfaces.0.get(&value))
+                {
+                    surface = found;
+                }
+
+                // Ice can also be painted on the Collision layer, see `CollisionBehaviour::Ice`.
+                if cell_value(ldtk_level, "Collision", level_translation, body_translation)
+                    .and_then(|value| behaviours.values.get(&value))
+                    == Some(&CollisionBehaviour::Ice)
+                {
+                    surface = Surface::Ice;
+                }
+            }
+        }
+
+        if current_surface.0 != surface {
+            current_surface.0 = surface;
+        }
+    }
+}
+
+/// The damping a body had before any surface changed it.
+#[derive(Component)]
+struct BaseDamping(f32);
+
+fn apply_surface_damping(
+    mut commands: Commands,
+    mut body_query: Query<
+        (Entity, &mut Damping, &CurrentSurface, Option<&BaseDamping>),
+        Changed<CurrentSurface>,
+    >,
+) {
+    for (entity, mut damping, current_surface, base_damping) in &mut body_query {
+        let base = match base_damping {
+            Some(base_damping) => base_damping.0,
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(BaseDamping(damping.linear_damping));
+                damping.linear_damping
+            }
+        };
+
+        damping.linear_damping = base * current_surface.0.damping_factor();
+    }
+}
+
+/// Players are carried by their movement controller instead, which would cancel this out.
+fn apply_conveyors(
+    mut body_query: Query<(&mut Velocity, &Damping, &CurrentSurface), Without<Player>>,
+    time: Res<Time>,
+) {
+    for (mut velocity, damping, current_surface) in &mut body_query {
+        // Pushing against the body's damping settles it at the conveyor speed.
+        velocity.linvel +=
+            current_surface.0.drift() * damping.linear_damping.max(1.0) * time.delta_seconds();
+    }
+}
@@ -5,6 +5,7 @@ pub struct SwitchPlugin;
 impl Plugin for SwitchPlugin {
     fn build(&self, app: &mut App) {
         app.register_ldtk_entity::<SwitchBundle>("Switch")
+            .register_ldtk_entity::<CoopSwitchBundle>("CoopSwitch")
             .add_event::<SwitchPressedEvent>()
             .add_system(button_collisons.run_in_state(GameState::Game));
     }
@@ -33,14 +34,38 @@ struct SwitchBundle {
     sprite_bundle: SpriteSheetBundle,
 }
 
+/// A switch only pressed while both co-op players stand on it, echoes and boxes don't count.
+#[derive(Bundle, Default, LdtkEntity)]
+struct CoopSwitchBundle {
+    switch: Switch,
+    coop_switch: CoopSwitch,
+    collision_count: CollisionCount,
+
+    #[from_entity_instance]
+    instance: EntityInstance,
+
+    #[bundle]
+    collider_bundle: SwitchCollider,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
 #[derive(Component, Default)]
 pub struct Switch;
 
+#[derive(Component, Default)]
+pub struct CoopSwitch;
+
+const COOP_SWITCH_PLAYERS: i32 = 2;
+
 #[derive(Bundle)]
 struct SwitchCollider {
     collider: Collider,
     event: ActiveEvents,
     sensor: Sensor,
+    collision_groups: CollisionGroups,
 }
 
 impl Default for SwitchCollider {
@@ -49,6 +74,7 @@ impl Default for SwitchCollider {
             collider: Collider::cuboid(7.0, 7.0),
             event: ActiveEvents::COLLISION_EVENTS,
             sensor: Sensor,
+            collision_groups: CollisionGroups::new(SENSOR_GROUP, BODY_GROUPS),
         }
     }
 }
@@ -58,25 +84,52 @@ struct CollisionCount(i32);
 
 fn button_collisons(
     mut collision_events: EventReader<CollisionEvent>,
-    mut button_query: Query<(&mut TextureAtlasSprite, &mut CollisionCount, Entity), With<Switch>>,
+    mut button_query: Query<
+        (
+            &mut TextureAtlasSprite,
+            &mut CollisionCount,
+            Entity,
+            Option<&CoopSwitch>,
+        ),
+        With<Switch>,
+    >,
+    player_query: Query<(), With<Player>>,
     mut pressed_event: EventWriter<SwitchPressedEvent>,
 ) {
     for collison in collision_events.iter() {
-        for (mut sprite, mut collisions, entity) in &mut button_query {
+        for (mut sprite, mut collisions, entity, coop_switch) in &mut button_query {
+            let required = if coop_switch.is_some() {
+                COOP_SWITCH_PLAYERS
+            } else {
+                1
+            };
+            // Co-op switches only count the players standing on them.
+            let counts = |other: &Entity| coop_switch.is_none() || player_query.contains(*other);
+
             match collison {
                 CollisionEvent::Started(object_1, object_2, _) => {
-                    if &entity == object_1 || &entity == object_2 {
+                    let other = if &entity == object_1 {
+                        object_2
+                    } else {
+                        object_1
+                    };
+                    if (&entity == object_1 || &entity == object_2) && counts(other) {
                         collisions.0 += 1;
-                        if collisions.0 > 0 {
+                        if collisions.0 >= required {
                             sprite.index = 1;
                             pressed_event.send(SwitchPressedEvent(entity, SwitchState::Pressed))
                         };
                     }
                 }
                 CollisionEvent::Stopped(object_1, object_2, _) => {
-                    if &entity == object_1 || &entity == object_2 {
+                    let other = if &entity == object_1 {
+                        object_2
+                    } else {
+                        object_1
+                    };
+                    if (&entity == object_1 || &entity == object_2) && counts(other) {
                         collisions.0 -= 1;
-                        if collisions.0 == 0 {
+                        if collisions.0 == required - 1 {
                             sprite.index = 0;
                             pressed_event.send(SwitchPressedEvent(entity, SwitchState::Released))
                         };
@@ -0,0 +1,247 @@
+use super::*;
+
+pub struct TeleporterPlugin;
+
+impl Plugin for TeleporterPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_ldtk_entity::<TeleporterBundle>("Teleporter")
+            .add_system_set(
+                ConditionSet::new()
+                    .run_in_state(GameState::Game)
+                    .run_in_state(PauseState::Unpaused)
+                    .with_system(teleport_on_contact)
+                    .with_system(tick_teleport_cooldowns)
+                    .with_system(show_teleporter_power)
+                    .into(),
+            );
+    }
+}
+
+#[derive(Bundle, Default, LdtkEntity)]
+struct TeleporterBundle {
+    #[from_entity_instance]
+    teleporter: Teleporter,
+
+    #[from_entity_instance]
+    switches: DoorActivationControl,
+
+    #[from_entity_instance]
+    instance: EntityInstance,
+
+    #[bundle]
+    collider: TeleporterCollider,
+
+    #[bundle]
+    #[sprite_sheet_bundle]
+    sprite_bundle: SpriteSheetBundle,
+}
+
+#[derive(Component, Default)]
+pub struct Teleporter {
+    target: Option<String>,
+}
+
+impl From<EntityInstance> for Teleporter {
+    fn from(entity_instance: EntityInstance) -> Self {
+        match &entity_instance
+            .field_instances
+            .iter()
+            .find(|v| v.identifier == "Target")
+            .expect("Teleporter entity must have a target field")
+            .value
+        {
+            FieldValue::EntityRef(reference) => Self {
+                target: reference.as_ref().map(|v| v.entity_iid.clone()),
+            },
+            _ => panic!("Teleporter entity target field must be an entity ref"),
+        }
+    }
+}
+
+impl Teleporter {
+    fn is_powered(&self, activators: &DoorActivationControl) -> bool {
+        self.target.is_some() && (!activators.is_linked() || activators.is_active())
+    }
+}
+
+#[derive(Bundle)]
+struct TeleporterCollider {
+    collider: Collider,
+    event: ActiveEvents,
+    sensor: Sensor,
+    collision_groups: CollisionGroups,
+}
+
+impl Default for TeleporterCollider {
+    fn default() -> Self {
+        Self {
+            collider: Collider::cuboid(6.0, 6.0),
+            event: ActiveEvents::COLLISION_EVENTS,
+            sensor: Sensor,
+            collision_groups: CollisionGroups::new(SENSOR_GROUP, BODY_GROUPS),
+        }
+    }
+}
+
+/// Stops a body that has just arrived on a pad from being sent straight back.
+#[derive(Component)]
+struct TeleportCooldown(Timer);
+
+impl Default for TeleportCooldown {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, false))
+    }
+}
+
+/// How many recorded echo positions ahead are checked for a teleport the player already took.
+const ECHO_TELEPORT_LOOKAHEAD: usize = 4;
+
+#[allow(clippy::too_many_arguments)]
+fn teleport_on_contact(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionEvent>,
+    teleporter_query: Query<(
+        Entity,
+        &Teleporter,
+        &DoorActivationControl,
+        &EntityInstance,
+        &Transform,
+        &GlobalTransform,
+        &Parent,
+    )>,
+    mut body_query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            Option<&Player>,
+            Option<(&EchoOf, &mut EchoOffset)>,
+        ),
+        (
+            Or<(With<Player>, With<Box>, With<PastPlayer>)>,
+            Without<TeleportCooldown>,
+            Without<Teleporter>,
+        ),
+    >,
+    past_states_query: Query<&PlayerPastStates>,
+    parent_query: Query<&Parent, Without<Teleporter>>,
+    level_query: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    mut level_selection: ResMut<LevelSelection>,
+) {
+    for collision in collision_events.iter() {
+        let (object_1, object_2) = match collision {
+            CollisionEvent::Started(object_1, object_2, _) => (*object_1, *object_2),
+            CollisionEvent::Stopped(..) => continue,
+        };
+
+        let (pad, body) = if teleporter_query.contains(object_1) {
+            (object_1, object_2)
+        } else if teleporter_query.contains(object_2) {
+            (object_2, object_1)
+        } else {
+            continue;
+        };
+
+        let (_, teleporter, activators, ..) = teleporter_query.get(pad).unwrap();
+        if !teleporter.is_powered(activators) {
+            continue;
+        }
+
+        let target = teleporter_query
+            .iter()
+            .find(|(.., instance, _, _, _)| Some(&instance.iid) == teleporter.target.as_ref());
+
+        let (target_transform, target_global_transform, target_parent) = match target {
+            Some((_, _, _, _, target_transform, target_global_transform, target_parent)) => {
+                (target_transform, target_global_transform, target_parent)
+            }
+            None => {
+                warn!(
+                    "Teleporter target {:?} is not spawned, its level is probably not loaded",
+                    teleporter.target
+                );
+                continue;
+            }
+        };
+
+        if let Ok((mut transform, mut velocity, player, echo)) = body_query.get_mut(body) {
+            commands.entity(body).insert(TeleportCooldown::default());
+
+            if let Some((EchoOf(owner), mut offset)) = echo {
+                // The replay already holds the teleports the player took, only pads the player
+                // walked past move the echo and shift the rest of its replay.
+                let target_translation = target_global_transform.translation();
+                let replayed = past_states_query.get(*owner).map_or(false, |past_states| {
+                    past_states
+                        .upcoming_translations()
+                        .take(ECHO_TELEPORT_LOOKAHEAD)
+                        .any(|translation| {
+                            (translation + offset.0)
+                                .truncate()
+                                .distance(target_translation.truncate())
+                                < PLAYER_HALF_EXTENTS.max_element()
+                        })
+                });
+
+                if !replayed {
+                    let shift = (target_translation - transform.translation).truncate();
+                    offset.0 += shift.extend(0.0);
+                    transform.translation += shift.extend(0.0);
+                }
+                continue;
+            }
+
+            *velocity = Velocity::zero();
+
+            if player.is_some() {
+                // The player is worldly, so its translation is already in world space.
+                let target_translation = target_global_transform.translation();
+                transform.translation.x = target_translation.x;
+                transform.translation.y = target_translation.y;
+
+                if let Some(ldtk_level) = parent_query
+                    .get(target_parent.get())
+                    .ok()
+                    .and_then(|level_entity| level_query.get(level_entity.get()).ok())
+                    .and_then(|level_handle| ldtk_levels.get(level_handle))
+                {
+                    if !level_selection.is_match(&0, &ldtk_level.level) {
+                        *level_selection = LevelSelection::Iid(ldtk_level.level.iid.clone());
+                    }
+                }
+            } else {
+                commands.entity(target_parent.get()).add_child(body);
+                transform.translation.x = target_transform.translation.x;
+                transform.translation.y = target_transform.translation.y;
+            }
+        }
+    }
+}
+
+fn tick_teleport_cooldowns(
+    mut commands: Commands,
+    mut cooldown_query: Query<(Entity, &mut TeleportCooldown)>,
+    time: Res<Time>,
+) {
+    for (entity, mut cooldown) in &mut cooldown_query {
+        cooldown.0.tick(time.delta());
+        if cooldown.0.finished() {
+            commands.entity(entity).remove::<TeleportCooldown>();
+        }
+    }
+}
+
+fn show_teleporter_power(
+    mut teleporter_query: Query<
+        (&mut TextureAtlasSprite, &Teleporter, &DoorActivationControl),
+        Changed<DoorActivationControl>,
+    >,
+) {
+    for (mut sprite, teleporter, activators) in &mut teleporter_query {
+        sprite.index = if teleporter.is_powered(activators) {
+            1
+        } else {
+            0
+        };
+    }
+}
@@ -6,149 +6,526 @@ pub struct WallsPlugin;
 
 impl Plugin for WallsPlugin {
     fn build(&self, app: &mut App) {
-        app.register_ldtk_int_cell_for_layer::<WallBundle>("Collision", 1)
+        app.init_resource::<WallColliderSettings>()
+            .init_resource::<CollisionBehaviours>()
+            .insert_resource(PhysicsHooksWithQueryResource::<WallHookData>(
+                std::boxed::Box::new(OneWayGateHooks),
+            ))
+            .register_default_ldtk_int_cell_for_layer::<WallBundle>("Collision")
+            .add_enter_system(GameState::Game, load_collision_behaviours)
             .add_system(spawn_wall_collision.run_in_state(GameState::Game));
     }
 }
 
+#[derive(Default)]
+pub struct WallColliderSettings {
+    pub decomposition: WallDecomposition,
+    /// Spawn one compound collider per level instead of one collider per rectangle.
+    pub compound: bool,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WallDecomposition {
+    /// Merge row plates vertically, see [`merge_grid_rects`].
+    #[default]
+    RowMerge,
+    /// Repeatedly take the largest rectangle, see [`cover_grid_rects`].
+    LargestFirst,
+}
+
+impl WallDecomposition {
+    pub fn decompose(&self, cells: &HashSet<GridCoords>, width: i32, height: i32) -> Vec<GridRect> {
+        match self {
+            WallDecomposition::RowMerge => merge_grid_rects(cells, width, height),
+            WallDecomposition::LargestFirst => cover_grid_rects(cells, width, height),
+        }
+    }
+}
+
+/// How a value of the `Collision` IntGrid layer behaves, chosen by the value's identifier in
+/// LDtk so new tile kinds only need to be named there.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CollisionBehaviour {
+    /// Blocks everything.
+    Solid,
+    /// Blocks only echoes of the player.
+    EchoOnly,
+    /// Blocks only the player.
+    PlayerOnly,
+    /// Blocks only boxes.
+    BoxBarrier,
+    /// Can only be crossed while moving in the given direction.
+    OneWay(GateDirection),
+    /// Not a wall, a floor that bodies slide across.
+    Ice,
+    /// Not a wall, the cell gets a hazard sensor instead.
+    Hazard(Hazard),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GateDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl GateDirection {
+    fn vector(&self) -> Vec2 {
+        match self {
+            GateDirection::Up => Vec2::Y,
+            GateDirection::Down => Vec2::NEG_Y,
+            GateDirection::Left => Vec2::NEG_X,
+            GateDirection::Right => Vec2::X,
+        }
+    }
+}
+
+impl CollisionBehaviour {
+    fn from_identifier(identifier: &str) -> Option<Self> {
+        match identifier {
+            "Wall" | "Solid" => Some(CollisionBehaviour::Solid),
+            "EchoWall" => Some(CollisionBehaviour::EchoOnly),
+            "PlayerWall" => Some(CollisionBehaviour::PlayerOnly),
+            "BoxBarrier" => Some(CollisionBehaviour::BoxBarrier),
+            "OneWayUp" => Some(CollisionBehaviour::OneWay(GateDirection::Up)),
+            "OneWayDown" => Some(CollisionBehaviour::OneWay(GateDirection::Down)),
+            "OneWayLeft" => Some(CollisionBehaviour::OneWay(GateDirection::Left)),
+            "OneWayRight" => Some(CollisionBehaviour::OneWay(GateDirection::Right)),
+            "Ice" => Some(CollisionBehaviour::Ice),
+            "Pit" => Some(CollisionBehaviour::Hazard(Hazard::Pit)),
+            "Spikes" => Some(CollisionBehaviour::Hazard(Hazard::Spikes)),
+            "Lava" => Some(CollisionBehaviour::Hazard(Hazard::Lava)),
+            _ => None,
+        }
+    }
+
+    fn collision_groups(&self) -> Option<CollisionGroups> {
+        match self {
+            CollisionBehaviour::Solid => Some(CollisionGroups::new(WALL_GROUP, ALL_GROUPS)),
+            CollisionBehaviour::EchoOnly => Some(CollisionGroups::new(WALL_GROUP, ECHO_GROUP)),
+            CollisionBehaviour::PlayerOnly => Some(CollisionGroups::new(WALL_GROUP, PLAYER_GROUP)),
+            CollisionBehaviour::BoxBarrier => Some(CollisionGroups::new(WALL_GROUP, BOX_GROUP)),
+            CollisionBehaviour::OneWay(_) => Some(CollisionGroups::new(WALL_GROUP, BODY_GROUPS)),
+            CollisionBehaviour::Ice | CollisionBehaviour::Hazard(_) => None,
+        }
+    }
+
+    /// Whether cells with this behaviour get a collider, floors and hazards are looked up by
+    /// cell instead.
+    pub fn is_wall(&self) -> bool {
+        !matches!(
+            self,
+            CollisionBehaviour::Ice | CollisionBehaviour::Hazard(_)
+        )
+    }
+}
+
+/// Maps values of the `Collision` IntGrid layer to their behaviour.
+#[derive(Default)]
+pub struct CollisionBehaviours {
+    pub values: HashMap<i32, CollisionBehaviour>,
+    /// The first value that behaves as a solid wall, used when cells turn into walls at runtime.
+    pub solid_value: Option<i32>,
+    /// The value of floor cells that crumble into walls once walked over.
+    pub crumbling_value: Option<i32>,
+}
+
+fn load_collision_behaviours(
+    mut behaviours: ResMut<CollisionBehaviours>,
+    levels: Res<LevelsAsset>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    let layer = ldtk_assets
+        .get(&levels.map)
+        .expect("Levels should be loaded by this point")
+        .project
+        .defs
+        .layers
+        .iter()
+        .find(|v| v.identifier == "Collision")
+        .expect("Project should have a Collision layer");
+
+    behaviours.values = layer
+        .int_grid_values
+        .iter()
+        .filter_map(|value| {
+            let behaviour = match &value.identifier {
+                Some(identifier) => CollisionBehaviour::from_identifier(identifier),
+                // Unnamed walls predate the behaviour table.
+                None if value.value == 1 => Some(CollisionBehaviour::Solid),
+                None => None,
+            };
+            behaviour.map(|behaviour| (value.value, behaviour))
+        })
+        .collect();
+
+    behaviours.solid_value = layer
+        .int_grid_values
+        .iter()
+        .map(|value| value.value)
+        .filter(|value| behaviours.values.get(value) == Some(&CollisionBehaviour::Solid))
+        .min();
+
+    behaviours.crumbling_value = layer
+        .int_grid_values
+        .iter()
+        .find(|value| value.identifier.as_deref() == Some("Crumble"))
+        .map(|value| value.value);
+}
+
 #[derive(LdtkIntCell, Bundle)]
 struct WallBundle {
     wall: Wall,
 }
 
 #[derive(Component, Default)]
-struct Wall;
+pub struct Wall;
+
+/// A solid wall whose contacts are dropped for bodies moving through it the allowed way.
+#[derive(Component)]
+pub struct OneWayGate(GateDirection);
+
+/// The components rapier's physics hooks read, passed to `RapierPhysicsPlugin`.
+pub type WallHookData = &'static OneWayGate;
+
+/// Contacts pushing a body against the gate's direction are kept, so the gate only blocks
+/// bodies coming from its far side.
+const GATE_BLOCK_THRESHOLD: f32 = 0.5;
+
+struct OneWayGateHooks;
+
+impl PhysicsHooksWithQuery<WallHookData> for OneWayGateHooks {
+    fn modify_solver_contacts(
+        &self,
+        context: ContactModificationContextView,
+        gate_query: &Query<WallHookData>,
+    ) {
+        let (gate, body_is_second) = match (
+            gate_query.get(context.collider1()),
+            gate_query.get(context.collider2()),
+        ) {
+            (Ok(gate), _) => (gate, true),
+            (_, Ok(gate)) => (gate, false),
+            _ => return,
+        };
+
+        // The contact normal points from the first collider towards the second.
+        let normal = Vec2::new(context.raw.normal.x, context.raw.normal.y);
+        let towards_body = if body_is_second { normal } else { -normal };
+
+        if towards_body.dot(gate.0.vector()) < GATE_BLOCK_THRESHOLD {
+            context.raw.solver_contacts.clear();
+        }
+    }
+}
+
+/// The collision cells of a level and the merged colliders currently spawned for them.
+#[derive(Component)]
+struct LevelWalls {
+    width: i32,
+    height: i32,
+    grid_size: f32,
+    cells: HashMap<GridCoords, CollisionBehaviour>,
+    rects: HashMap<CollisionBehaviour, Vec<(GridRect, Entity)>>,
+}
+
+impl LevelWalls {
+    /// Releases the colliders of `behaviour` covering any of `touching` into `despawned`,
+    /// queueing their remaining cells to be merged again.
+    fn release_rects(
+        &mut self,
+        behaviour: CollisionBehaviour,
+        touching: &[GridCoords],
+        dirty: &mut HashMap<CollisionBehaviour, HashSet<GridCoords>>,
+        despawned: &mut HashSet<Entity>,
+    ) {
+        let rects = self.rects.entry(behaviour).or_default();
+        let (released, kept): (Vec<_>, Vec<_>) = rects
+            .drain(..)
+            .partition(|(rect, _)| touching.iter().any(|&coords| rect.contains(coords)));
+        *rects = kept;
+
+        let dirty_cells = dirty.entry(behaviour).or_default();
+        for (rect, entity) in released {
+            despawned.insert(entity);
+
+            for x in rect.left..=rect.right {
+                for y in rect.bottom..=rect.top {
+                    let coords = GridCoords { x, y };
+                    if self.cells.get(&coords) == Some(&behaviour) {
+                        dirty_cells.insert(coords);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Updates the cells and releases the colliders touching any change.
+    ///
+    /// Returns the cells of each behaviour that need merging again and the colliders to
+    /// despawn.
+    fn apply_changes(
+        &mut self,
+        changes: &[(GridCoords, Option<CollisionBehaviour>)],
+        compound: bool,
+    ) -> (
+        HashMap<CollisionBehaviour, HashSet<GridCoords>>,
+        HashSet<Entity>,
+    ) {
+        let mut dirty: HashMap<CollisionBehaviour, HashSet<GridCoords>> = HashMap::new();
+        let mut despawned = HashSet::new();
+
+        for &(coords, behaviour) in changes {
+            let previous = self.cells.get(&coords).copied();
+            if previous == behaviour {
+                continue;
+            }
+
+            if let Some(previous) = previous {
+                self.cells.remove(&coords);
+                self.release_rects(previous, &[coords], &mut dirty, &mut despawned);
+            }
+
+            if let Some(behaviour) = behaviour {
+                self.cells.insert(coords, behaviour);
+                dirty.entry(behaviour).or_default().insert(coords);
+
+                // Let the new cell merge with the rectangles next to it.
+                let neighbours = [(0, 1), (0, -1), (1, 0), (-1, 0)].map(|(x, y)| GridCoords {
+                    x: coords.x + x,
+                    y: coords.y + y,
+                });
+                self.release_rects(behaviour, &neighbours, &mut dirty, &mut despawned);
+            }
+        }
 
+        if compound {
+            for (&behaviour, cells) in &mut dirty {
+                let all_cells = self
+                    .cells
+                    .iter()
+                    .filter(|(_, &v)| v == behaviour)
+                    .map(|(&coords, _)| coords)
+                    .collect::<Vec<_>>();
+                self.release_rects(behaviour, &all_cells, &mut HashMap::new(), &mut despawned);
+                cells.extend(all_cells);
+            }
+        }
+
+        // Cells released by an earlier change in the same batch may have changed since.
+        for (behaviour, cells) in &mut dirty {
+            cells.retain(|coords| self.cells.get(coords) == Some(behaviour));
+        }
+
+        (dirty, despawned)
+    }
+}
+
+/// Keeps wall colliders in sync with the `Collision` layer.
+///
+/// Only the merged rectangles touching a changed cell are despawned and merged again, so
+/// changing a cell at runtime doesn't respawn the rest of the level. Compound colliders cover
+/// a whole behaviour, so with [`WallColliderSettings::compound`] the behaviour's collider is
+/// rebuilt instead.
 fn spawn_wall_collision(
     mut commands: Commands,
-    wall_query: Query<(&GridCoords, &Parent), Added<Wall>>,
+    wall_query: Query<(&GridCoords, &IntGridCell, &Parent), (With<Wall>, Changed<IntGridCell>)>,
     parent_query: Query<&Parent, Without<Wall>>,
-    level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+    mut level_query: Query<(&Handle<LdtkLevel>, Option<&mut LevelWalls>)>,
     levels: Res<Assets<LdtkLevel>>,
+    settings: Res<WallColliderSettings>,
+    behaviours: Res<CollisionBehaviours>,
 ) {
-    #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash)]
-    struct Plate {
-        left: i32,
-        right: i32,
-    }
+    let mut level_to_changes: HashMap<Entity, Vec<(GridCoords, Option<CollisionBehaviour>)>> =
+        HashMap::new();
 
-    #[derive(Copy, Clone, PartialEq, Debug)]
-    pub struct Rect {
-        pub left: i32,
-        pub right: i32,
-        pub top: i32,
-        pub bottom: i32,
-    }
-
-    let mut level_to_wall_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
-
-    wall_query.for_each(|(&grid_coords, parent)| {
+    wall_query.for_each(|(&grid_coords, int_grid_cell, parent)| {
         if let Ok(level_entity) = parent_query.get(parent.get()) {
-            level_to_wall_locations
+            level_to_changes
                 .entry(level_entity.get())
-                .or_insert(HashSet::new())
-                .insert(grid_coords);
+                .or_insert(Vec::new())
+                .push((
+                    grid_coords,
+                    behaviours
+                        .values
+                        .get(&int_grid_cell.value)
+                        .copied()
+                        .filter(CollisionBehaviour::is_wall),
+                ));
         }
     });
 
-    if !wall_query.is_empty() {
-        level_query.for_each(|(level_entity, level_handle)| {
-            if let Some(level_walls) = level_to_wall_locations.get(&level_entity) {
-                let LayerInstance {
-                    c_wid: width,
-                    c_hei: height,
-                    grid_size,
-                    ..
-                } = *levels
-                    .get(level_handle)
-                    .expect("Level should be loaded by this point")
-                    .level
-                    .layer_instances
-                    .clone()
-                    .expect("Level asset should have layers")
-                    .iter()
-                    .find(|v| v.identifier == "Collision")
-                    .expect("Level should have a Walls layer");
-
-                let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
-
-                for y in 0..height {
-                    let mut row_plates: Vec<Plate> = Vec::new();
-                    let mut plate_start = None;
-
-                    for x in 0..width + 1 {
-                        match (plate_start, level_walls.contains(&GridCoords { x, y })) {
-                            (Some(s), false) => {
-                                row_plates.push(Plate {
-                                    left: s,
-                                    right: x - 1,
-                                });
-                                plate_start = None;
-                            }
-                            (None, true) => plate_start = Some(x),
-                            _ => (),
-                        }
-                    }
+    for (level_entity, changes) in level_to_changes {
+        if let Ok((level_handle, existing_walls)) = level_query.get_mut(level_entity) {
+            let mut new_walls = None;
+            let level_walls = match existing_walls {
+                Some(level_walls) => level_walls.into_inner(),
+                None => {
+                    let LayerInstance {
+                        c_wid: width,
+                        c_hei: height,
+                        grid_size,
+                        ..
+                    } = *levels
+                        .get(level_handle)
+                        .expect("Level should be loaded by this point")
+                        .level
+                        .layer_instances
+                        .clone()
+                        .expect("Level asset should have layers")
+                        .iter()
+                        .find(|v| v.identifier == "Collision")
+                        .expect("Level should have a Walls layer");
 
-                    plate_stack.push(row_plates);
+                    new_walls.insert(LevelWalls {
+                        width,
+                        height,
+                        grid_size: grid_size as f32,
+                        cells: HashMap::new(),
+                        rects: HashMap::new(),
+                    })
                 }
+            };
 
-                let mut wall_rects: Vec<Rect> = Vec::new();
-                let mut previous_rects: HashMap<Plate, Rect> = HashMap::new();
-
-                plate_stack.push(Vec::new());
-
-                for (y, row) in plate_stack.iter().enumerate() {
-                    let mut current_rects: HashMap<Plate, Rect> = HashMap::new();
-                    for plate in row {
-                        if let Some(previous_rect) = previous_rects.remove(plate) {
-                            current_rects.insert(
-                                *plate,
-                                Rect {
-                                    top: previous_rect.top + 1,
-                                    ..previous_rect
-                                },
-                            );
-                        } else {
-                            current_rects.insert(
-                                *plate,
-                                Rect {
-                                    bottom: y as i32,
-                                    top: y as i32,
-                                    left: plate.left,
-                                    right: plate.right,
-                                },
-                            );
-                        }
-                    }
+            let (dirty, despawned) = level_walls.apply_changes(&changes, settings.compound);
+            for entity in despawned {
+                commands.entity(entity).despawn_recursive();
+            }
 
-                    wall_rects.append(&mut previous_rects.values().copied().collect());
-                    previous_rects = current_rects;
+            let LevelWalls {
+                width,
+                height,
+                grid_size,
+                ..
+            } = *level_walls;
+
+            for (behaviour, cells) in dirty {
+                if cells.is_empty() {
+                    continue;
                 }
 
-                for wall_rect in wall_rects {
-                    commands.entity(level_entity).with_children(|builder| {
-                        builder
-                            .spawn()
-                            .insert_bundle(TransformBundle::from(Transform::from_xyz(
-                                (wall_rect.left + wall_rect.right + 1) as f32 * grid_size as f32
-                                    / 2.,
-                                (wall_rect.bottom + wall_rect.top + 1) as f32 * grid_size as f32
-                                    / 2.,
-                                0.0,
+                let wall_rects = settings.decomposition.decompose(&cells, width, height);
+                let spawned = level_walls.rects.entry(behaviour).or_default();
+
+                commands.entity(level_entity).with_children(|builder| {
+                    if settings.compound {
+                        let shapes = wall_rects
+                            .iter()
+                            .map(|wall_rect| {
+                                let half_extents = wall_rect.half_extents(grid_size);
+                                (
+                                    wall_rect.centre(grid_size),
+                                    0.0,
+                                    Collider::cuboid(half_extents.x, half_extents.y),
+                                )
+                            })
+                            .collect::<Vec<_>>();
+
+                        let mut wall = builder.spawn();
+                        wall.insert_bundle(TransformBundle::default())
+                            .insert(Collider::compound(shapes));
+                        insert_behaviour(&mut wall, behaviour);
+
+                        let entity = wall.id();
+                        spawned.extend(wall_rects.iter().map(|&wall_rect| (wall_rect, entity)));
+                    } else {
+                        for wall_rect in wall_rects {
+                            let half_extents = wall_rect.half_extents(grid_size);
+
+                            let mut wall = builder.spawn();
+                            wall.insert_bundle(TransformBundle::from(Transform::from_translation(
+                                wall_rect.centre(grid_size).extend(0.0),
                             )))
-                            .insert(RigidBody::Fixed)
-                            .insert(Collider::cuboid(
-                                (wall_rect.right as f32 - wall_rect.left as f32 + 1.)
-                                    * grid_size as f32
-                                    / 2.0,
-                                (wall_rect.top as f32 - wall_rect.bottom as f32 + 1.)
-                                    * grid_size as f32
-                                    / 2.0,
-                            ));
-                    });
-                }
+                            .insert(Collider::cuboid(half_extents.x, half_extents.y));
+                            insert_behaviour(&mut wall, behaviour);
+
+                            spawned.push((wall_rect, wall.id()));
+                        }
+                    }
+                });
             }
-        })
+
+            if let Some(level_walls) = new_walls {
+                commands.entity(level_entity).insert(level_walls);
+            }
+        }
+    }
+}
+
+fn insert_behaviour(wall: &mut bevy::ecs::system::EntityCommands, behaviour: CollisionBehaviour) {
+    if let Some(collision_groups) = behaviour.collision_groups() {
+        wall.insert(RigidBody::Fixed).insert(collision_groups);
+    }
+
+    if let CollisionBehaviour::OneWay(direction) = behaviour {
+        wall.insert(ActiveHooks::MODIFY_SOLVER_CONTACTS)
+            .insert(OneWayGate(direction));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level_walls(cells: &[GridCoords], rects: &[(GridRect, Entity)]) -> LevelWalls {
+        LevelWalls {
+            width: 8,
+            height: 8,
+            grid_size: 8.0,
+            cells: cells
+                .iter()
+                .map(|&coords| (coords, CollisionBehaviour::Solid))
+                .collect(),
+            rects: HashMap::from([(CollisionBehaviour::Solid, rects.to_vec())]),
+        }
+    }
+
+    #[test]
+    fn retracting_a_run_in_one_frame_leaves_no_collider() {
+        let run = [0, 1, 2].map(|x| GridCoords { x, y: 3 });
+        let rect = GridRect {
+            left: 0,
+            right: 2,
+            top: 3,
+            bottom: 3,
+        };
+        let entity = Entity::from_raw(1);
+        let mut walls = level_walls(&run, &[(rect, entity)]);
+
+        let changes = run.map(|coords| (coords, None));
+        let (dirty, despawned) = walls.apply_changes(&changes, false);
+
+        assert!(despawned.contains(&entity));
+        assert!(walls.cells.is_empty());
+        for (_, cells) in dirty {
+            for rect in WallDecomposition::default().decompose(&cells, walls.width, walls.height) {
+                assert!(run.iter().all(|&coords| !rect.contains(coords)));
+            }
+        }
+        for rects in walls.rects.values() {
+            assert!(rects
+                .iter()
+                .all(|(rect, _)| run.iter().all(|&coords| !rect.contains(coords))));
+        }
+    }
+
+    #[test]
+    fn retracting_part_of_a_run_keeps_the_rest() {
+        let run = [0, 1, 2].map(|x| GridCoords { x, y: 0 });
+        let rect = GridRect {
+            left: 0,
+            right: 2,
+            top: 0,
+            bottom: 0,
+        };
+        let mut walls = level_walls(&run, &[(rect, Entity::from_raw(1))]);
+
+        let (dirty, _) = walls.apply_changes(&[(run[1], None)], false);
+
+        assert_eq!(
+            dirty.get(&CollisionBehaviour::Solid),
+            Some(&HashSet::from([run[0], run[2]]))
+        );
     }
 }